@@ -9,8 +9,8 @@ use my_strategy::models::*;
 use my_strategy::config::Config;
 
 fn bench(c: &mut Criterion) {
-    Config::default().init_singleton();
-    c.bench_function("Player::can_eat_blob", |b| {
+    let config = Config::default();
+    c.bench_function("Player::can_eat_blob", move |b| {
         b.iter(|| {
             (0..100)
                 .map(|i| {
@@ -23,7 +23,7 @@ fn bench(c: &mut Criterion) {
                         },
                         point_: Point::new(100.0 + i, 200.0 + i),
                         m_: m,
-                        r_: Player::mass_to_radius(m),
+                        r_: Player::mass_to_radius(m, &config),
                         v_: Default::default(),
                         is_fast_: Default::default(),
                         ttf_: Default::default(),
@@ -36,12 +36,12 @@ fn bench(c: &mut Criterion) {
                         },
                         point_: Point::new(100.0 - i, 200.0 - i),
                         m_: m,
-                        r_: Player::mass_to_radius(m),
+                        r_: Player::mass_to_radius(m, &config),
                         v_: Default::default(),
                         is_fast_: Default::default(),
                         ttf_: Default::default(),
                     };
-                    player.can_eat_blob(&other) as i64
+                    player.can_eat_blob(&other, &config) as i64
                 })
                 .sum::<i64>()
         })
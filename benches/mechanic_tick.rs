@@ -0,0 +1,109 @@
+#[macro_use]
+extern crate criterion;
+
+extern crate my_strategy;
+
+use criterion::Criterion;
+
+use my_strategy::command::Command;
+use my_strategy::config::Config;
+use my_strategy::models::*;
+use my_strategy::strategy::grid::Grid;
+use my_strategy::strategy::mechanic::{Mechanic, State};
+
+const FOOD_COUNT: usize = 2000;
+const CROWD_SIZE: usize = 40;
+const TICKS: usize = 5;
+
+fn food(config: &Config) -> Vec<Food> {
+    (0..FOOD_COUNT)
+        .map(|i| {
+            let point = Point::new((i * 37 % 990) as f64, (i * 91 % 990) as f64);
+            Food {
+                id_: FoodId {
+                    x10: (point.x * 10.0).floor() as u32,
+                    y10: (point.y * 10.0).floor() as u32,
+                },
+                point_: point,
+                r_: config.food_radius,
+                m_: config.food_mass,
+            }
+        })
+        .collect()
+}
+
+fn sparse_state(config: &Config) -> State {
+    let mut state = State::default();
+    state.my_blobs.push(Player::new(1, 0, Point::new(300.0, 300.0), 100.0, config));
+    for i in 0..3 {
+        let point = Point::new(400.0 + 50.0 * i as f64, 350.0);
+        state.enemies.push(Player::new(2 + i as PlayerId, 0, point, 60.0, config));
+    }
+    state
+}
+
+// More blobs than the grid indexes, so player collisions and eating go through the index too.
+fn crowded_state(config: &Config) -> State {
+    let mut state = State::default();
+    state.my_blobs.push(Player::new(1, 0, Point::new(300.0, 300.0), 100.0, config));
+    for i in 0..CROWD_SIZE {
+        let point = Point::new((i * 173 % 950) as f64 + 20.0, (i * 311 % 950) as f64 + 20.0);
+        let player_id = 2 + (i % 3) as PlayerId;
+        let m = 40.0 + i as f64;
+        state.enemies.push(Player::new(player_id, i as FragmentId, point, m, config));
+    }
+    state
+}
+
+fn bench_state(c: &mut Criterion, name: &str, state: fn(&Config) -> State) {
+    c.bench_function(&format!("Mechanic::tick {} linear scan", name), move |b| {
+        let config = Config::default();
+        let food = food(&config);
+        let state = state(&config);
+        let command = Command::from_point(Point::new(600.0, 600.0), &config);
+        b.iter(|| {
+            let mut mechanic = Mechanic::new(&state, &config);
+            mechanic.set_linear_scan();
+            for _ in 0..TICKS {
+                mechanic.tick(&command, &food);
+            }
+            mechanic.state.my_blobs.len()
+        })
+    });
+    c.bench_function(&format!("Mechanic::tick {} own grid", name), move |b| {
+        let config = Config::default();
+        let food = food(&config);
+        let state = state(&config);
+        let command = Command::from_point(Point::new(600.0, 600.0), &config);
+        b.iter(|| {
+            let mut mechanic = Mechanic::new(&state, &config);
+            for _ in 0..TICKS {
+                mechanic.tick(&command, &food);
+            }
+            mechanic.state.my_blobs.len()
+        })
+    });
+    c.bench_function(&format!("Mechanic::tick {} shared grid", name), move |b| {
+        let config = Config::default();
+        let food = food(&config);
+        let grid = Grid::with_blobs(&config, &food);
+        let state = state(&config);
+        let command = Command::from_point(Point::new(600.0, 600.0), &config);
+        b.iter(|| {
+            let mut mechanic = Mechanic::new(&state, &config);
+            mechanic.set_food_grid(&grid);
+            for _ in 0..TICKS {
+                mechanic.tick(&command, &food);
+            }
+            mechanic.state.my_blobs.len()
+        })
+    });
+}
+
+fn bench(c: &mut Criterion) {
+    bench_state(c, "sparse", sparse_state);
+    bench_state(c, "crowded", crowded_state);
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
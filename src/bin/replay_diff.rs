@@ -0,0 +1,5 @@
+extern crate my_strategy;
+
+fn main() {
+    my_strategy::replay_diff::run();
+}
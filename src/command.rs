@@ -1,10 +1,11 @@
-use config::config;
+use config::Config;
 use models::*;
 
 #[derive(Debug, Clone, Default)]
 pub struct Command {
     point_: Point,
     split_: bool,
+    eject_: bool,
     #[cfg(feature = "debug")]
     pause_: bool,
     debug_messages_: Vec<String>,
@@ -25,16 +26,16 @@ impl Command {
         Default::default()
     }
 
-    pub fn from_point(point: Point) -> Command {
+    pub fn from_point(point: Point, config: &Config) -> Command {
         let mut command = Command::new();
-        command.set_point(point);
+        command.set_point(point, config);
         command
     }
 
-    pub fn set_point(&mut self, point: Point) {
+    pub fn set_point(&mut self, point: Point, config: &Config) {
         self.point_ = Point::new(
-            point.x.max(0.0).min(config().game_width as f64),
-            point.y.max(0.0).min(config().game_height as f64),
+            point.x.max(0.0).min(config.game_width as f64),
+            point.y.max(0.0).min(config.game_height as f64),
         );
     }
 
@@ -46,6 +47,14 @@ impl Command {
         self.split_ = true;
     }
 
+    pub fn eject(&self) -> bool {
+        self.eject_
+    }
+
+    pub fn set_eject(&mut self) {
+        self.eject_ = true;
+    }
+
     #[cfg(feature = "debug")]
     pub fn pause(&self) -> bool {
         self.pause_
@@ -3,7 +3,7 @@ use std::sync::{Mutex, MutexGuard};
 
 use lazy_static;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[serde(default)]
 pub struct Config {
@@ -14,6 +14,7 @@ pub struct Config {
     pub diam_eat_factor: f64,
     pub ejection_mass: f64,
     pub ejection_radius: f64,
+    pub ejection_start_speed: f64,
     pub food_mass: f64,
     pub food_radius: f64,
     pub game_height: i64,
@@ -22,6 +23,7 @@ pub struct Config {
     pub mass_eat_factor: f64,
     pub max_frags_cnt: i64,
     pub min_burst_mass: f64,
+    pub min_ejection_mass: f64,
     pub min_shrink_mass: f64,
     pub min_split_mass: f64,
     pub rad_hurt_factor: f64,
@@ -31,7 +33,10 @@ pub struct Config {
     pub speed_factor: f64,
     pub split_start_speed: f64,
     pub ticks_til_fusion: i64,
+    pub virus_mass: f64,
     pub virus_radius: f64,
+    pub virus_split_mass: f64,
+    pub virus_split_speed: f64,
     pub vis_factor: f64,
     pub vis_factor_fr: f64,
     pub vis_shift: f64,
@@ -48,6 +53,7 @@ impl Default for Config {
             diam_eat_factor: 2.0 / 3.0,
             ejection_mass: 15.0,
             ejection_radius: 4.0,
+            ejection_start_speed: 8.0,
             food_mass: 1.0,
             food_radius: 2.5,
             game_height: 990,
@@ -56,6 +62,7 @@ impl Default for Config {
             mass_eat_factor: 1.2,
             max_frags_cnt: 10,
             min_burst_mass: 60.0,
+            min_ejection_mass: 40.0,
             min_shrink_mass: 100.0,
             min_split_mass: 120.0,
             rad_hurt_factor: 0.66,
@@ -65,7 +72,10 @@ impl Default for Config {
             speed_factor: 25.0,
             split_start_speed: 9.0,
             ticks_til_fusion: 250,
+            virus_mass: 40.0,
             virus_radius: 22.0,
+            virus_split_mass: 80.0,
+            virus_split_speed: 8.0,
             vis_factor: 4.0,
             vis_factor_fr: 2.5,
             vis_shift: 10.0,
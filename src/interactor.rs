@@ -1,60 +1,199 @@
-use std::io;
+use std::env;
+use std::io::{self, BufRead, Write};
 use std::num::ParseIntError;
 use std::str::FromStr;
+#[cfg(feature = "tools")]
+use std::time::Instant;
 
 use serde::de::DeserializeOwned;
 use serde_json;
 
 use strategy::*;
-use config::Config;
+use config::{config, Config};
+use protocol::*;
+#[cfg(feature = "tools")]
+use replay::ReplayWriter;
+
+#[cfg(feature = "tools")]
+const REPLAY_LOCATION_VAR: &str = "REPLAY_LOCATION";
+const STRATEGY_VAR: &str = "STRATEGY";
 
 pub fn run() {
-    read_config().init_singleton();
-    let mut strategy = get_strategy();
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    match read_config(&mut input) {
+        Some(config) => config.init_singleton(),
+        None => return,
+    }
+    let stdout = io::stdout();
+    play(&mut get_strategy(), config(), &mut input, &mut stdout.lock());
+}
+
+// Plays the game until the input ends. A tick that can't be read is played again with the last
+// data that could, or answered with safe_response if there is none yet.
+pub fn play<S, R, W>(strategy: &mut S, config: &Config, input: &mut R, output: &mut W)
+where
+    S: Strategy,
+    R: BufRead,
+    W: Write,
+{
+    #[cfg(feature = "tools")]
+    let mut replay = create_replay(config);
+    strategy.on_start(config);
     let mut tick = 0;
-    while let Some(data) = read_tick_data() {
-        let command = strategy.tick(
-            tick,
-            data.my_blobs,
-            data.food,
-            data.ejections,
-            data.viruses,
-            data.enemies,
-        );
-        print_command(command);
+    let mut last_data: Option<TickData> = None;
+    loop {
+        let data = match read_message(input) {
+            Ok(Some(Message::Tick(data))) => data,
+            Ok(Some(Message::GameOver(summary))) => {
+                strategy.on_game_over(&summary);
+                break;
+            }
+            Ok(Some(Message::Notice(notice))) => {
+                strategy.on_notice(&notice);
+                if notice.kind == KILL_NOTICE {
+                    break;
+                }
+                eprintln!("notice: {} {}", notice.kind, notice.message);
+                continue;
+            }
+            Ok(None) => break,
+            Err(error @ ProtocolError::Io(_)) => {
+                report_error(&error);
+                break;
+            }
+            Err(error) => {
+                report_error(&error);
+                match last_data {
+                    Some(ref data) => data.clone(),
+                    None => {
+                        let response = safe_response(config);
+                        print_response(output, &response);
+                        #[cfg(feature = "tools")]
+                        write_replay(&mut replay, |writer| {
+                            writer.write_safe_response(tick, response)
+                        });
+                        tick += 1;
+                        continue;
+                    }
+                }
+            }
+        };
+        let world = read_world(tick, &data, config);
+        #[cfg(feature = "tools")]
+        let start = Instant::now();
+        let command = strategy.tick(&world);
+        let response = Response::from_command(&command);
+        print_response(output, &response);
+        #[cfg(feature = "tools")]
+        {
+            let elapsed = start.elapsed();
+            write_replay(&mut replay, |writer| {
+                writer.write_tick(tick, data.clone(), response, elapsed)
+            });
+        }
+        last_data = Some(data);
         tick += 1;
     }
+    strategy.on_game_end();
 }
 
 #[cfg(not(feature = "debug"))]
-fn get_strategy() -> MyStrategy {
-    MyStrategy::new()
+fn get_strategy() -> Box<dyn Strategy> {
+    new_strategy()
 }
 
 #[cfg(feature = "debug")]
-fn get_strategy() -> TimingWrapper<MyStrategy> {
-    TimingWrapper::new(MyStrategy::new())
+fn get_strategy() -> TimingWrapper<Box<dyn Strategy>> {
+    TimingWrapper::new(new_strategy())
 }
 
-fn read_config() -> Config {
-    read_json().expect("EOF while reading config")
+fn new_strategy() -> Box<dyn Strategy> {
+    let scorer = Scorer::new(ScoreWeights::from_env().unwrap_or_else(|error| {
+        eprintln!("failed to load score weights: {}", error);
+        Default::default()
+    }));
+    let params = SearchParams::from_env().unwrap_or_else(|error| {
+        eprintln!("failed to load search params: {}", error);
+        Default::default()
+    });
+    match env::var(STRATEGY_VAR).as_ref().map(String::as_str) {
+        Ok("evolution") => {
+            let mut strategy = EvolutionStrategy::new();
+            strategy.set_scorer(scorer);
+            strategy.set_search_params(params);
+            Box::new(strategy)
+        }
+        Ok("mcts") => {
+            let mut strategy = MctsStrategy::new();
+            strategy.set_scorer(scorer);
+            strategy.set_search_params(params);
+            Box::new(strategy)
+        }
+        _ => {
+            let mut strategy = MyStrategy::new();
+            strategy.set_scorer(scorer);
+            strategy.set_search_params(params);
+            Box::new(strategy)
+        }
+    }
 }
 
-#[derive(Default)]
-struct Entities {
-    my_blobs: Vec<Player>,
-    food: Vec<Food>,
-    ejections: Vec<Ejection>,
-    viruses: Vec<Virus>,
-    enemies: Vec<Player>,
+// A replay that can't be written is dropped, the game goes on without it.
+#[cfg(feature = "tools")]
+fn create_replay(config: &Config) -> Option<ReplayWriter> {
+    let path = env::var(REPLAY_LOCATION_VAR).ok()?;
+    match ReplayWriter::create(&path, config) {
+        Ok(replay) => Some(replay),
+        Err(error) => {
+            eprintln!("failed to create replay {}: {}", path, error);
+            None
+        }
+    }
 }
 
-fn read_tick_data() -> Option<Entities> {
-    let data: TickData = read_json()?;
-    let mut entities = Entities::default();
-    for mine in data.mine {
+#[cfg(feature = "tools")]
+fn write_replay<F>(replay: &mut Option<ReplayWriter>, write: F)
+where
+    F: FnOnce(&mut ReplayWriter) -> io::Result<()>,
+{
+    let result = match *replay {
+        Some(ref mut writer) => write(writer),
+        None => return,
+    };
+    if let Err(error) = result {
+        eprintln!("failed to write replay, recording stopped: {}", error);
+        *replay = None;
+    }
+}
+
+fn read_config<R: BufRead>(input: &mut R) -> Option<Config> {
+    read_json(input).unwrap_or_else(|error| {
+        report_error(&error);
+        Some(Default::default())
+    })
+}
+
+pub fn read_world<'a>(tick: Tick, data: &TickData, config: &'a Config) -> World<'a> {
+    let mut world = World {
+        tick,
+        my_blobs: Default::default(),
+        food: Default::default(),
+        ejections: Default::default(),
+        viruses: Default::default(),
+        enemies: Default::default(),
+        config,
+    };
+    for mine in data.mine.iter() {
+        let id = match mine.id.parse() {
+            Ok(id) => id,
+            Err(error) => {
+                report_error(&ProtocolError::BadId(mine.id.clone(), error));
+                continue;
+            }
+        };
         let mut me = Player {
-            id_: mine.id.parse().expect("failed to parse my id"),
+            id_: id,
             point_: Point::new(mine.x, mine.y),
             m_: mine.m,
             r_: mine.r,
@@ -62,221 +201,265 @@ fn read_tick_data() -> Option<Entities> {
             is_fast_: Default::default(),
             ttf_: mine.ttf.unwrap_or(0),
         };
-        me.update_is_fast();
-        entities.my_blobs.push(me);
+        me.update_is_fast(config);
+        world.my_blobs.push(me);
     }
-    for obj in data.objects {
-        let point = Point::new(obj.x, obj.y);
-        match obj.t.as_ref() {
-            "F" => {
-                entities.food.push(Food {
-                    id_: FoodId {
-                        x10: (point.x * 10.0).floor() as u32,
-                        y10: (point.y * 10.0).floor() as u32,
-                    },
-                    point_: point,
-                });
-            }
-            "E" => {
-                entities.ejections.push(Ejection {
-                    id_: obj.id.expect("ejection has no id").parse().expect(
-                        "failed to parse ejection id",
-                    ),
-                    point_: point,
-                });
-            }
-            "V" => {
-                entities.viruses.push(Virus {
-                    id_: obj.id.expect("virus has no id").parse().expect(
-                        "failed to parse virus id",
-                    ),
-                    point_: point,
-                    m_: obj.m.expect("virus has no mass"),
-                });
-            }
-            "P" => {
-                entities.enemies.push(Player {
-                    id_: obj.id.expect("enemy has no id").parse().expect(
-                        "failed to parse enemy id",
-                    ),
-                    point_: point,
-                    m_: obj.m.expect("enemy has no mass"),
-                    r_: obj.r.expect("enemy has no radius"),
-                    v_: Default::default(),
-                    is_fast_: Default::default(),
-                    ttf_: 0,
-                });
-            }
-            _ => {
-                panic!("unknown object type");
-            }
+    for obj in data.objects.iter() {
+        if let Err(error) = read_object(&mut world, obj, config) {
+            report_error(&error);
         }
     }
-    Some(entities)
-}
-
-#[derive(Deserialize)]
-#[serde(rename_all = "PascalCase")]
-struct TickData {
-    mine: Vec<Mine>,
-    objects: Vec<Objects>,
+    world
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "PascalCase")]
-struct Mine {
-    id: String,
-    x: f64,
-    y: f64,
-    r: f64,
-    m: f64,
-    s_x: f64,
-    s_y: f64,
-    #[serde(rename = "TTF")]
-    ttf: Option<i64>,
+fn read_object(world: &mut World, obj: &Objects, config: &Config) -> Result<(), ProtocolError> {
+    let point = Point::new(obj.x, obj.y);
+    match obj.t.as_ref() {
+        "F" => {
+            world.food.push(Food {
+                id_: FoodId {
+                    x10: (point.x * 10.0).floor() as u32,
+                    y10: (point.y * 10.0).floor() as u32,
+                },
+                point_: point,
+                r_: config.food_radius,
+                m_: config.food_mass,
+            });
+        }
+        "E" => {
+            world.ejections.push(Ejection {
+                id_: parse_id(obj)?,
+                point_: point,
+                r_: config.ejection_radius,
+                m_: config.ejection_mass,
+                v_: Default::default(),
+            });
+        }
+        "V" => {
+            world.viruses.push(Virus {
+                id_: parse_id(obj)?,
+                point_: point,
+                r_: config.virus_radius,
+                m_: obj.m.ok_or_else(|| ProtocolError::MissingMass(obj.t.clone()))?,
+                v_: Default::default(),
+                split_angle_: Default::default(),
+            });
+        }
+        "P" => {
+            world.enemies.push(Player {
+                id_: parse_id(obj)?,
+                point_: point,
+                m_: obj.m.ok_or_else(|| ProtocolError::MissingMass(obj.t.clone()))?,
+                r_: obj.r.ok_or_else(|| ProtocolError::MissingRadius(obj.t.clone()))?,
+                v_: Default::default(),
+                is_fast_: Default::default(),
+                ttf_: 0,
+            });
+        }
+        _ => {
+            return Err(ProtocolError::UnknownObjectType(obj.t.clone()));
+        }
+    }
+    Ok(())
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "PascalCase")]
-struct Objects {
-    id: Option<String>,
-    t: String,
-    x: f64,
-    y: f64,
-    m: Option<f64>,
-    r: Option<f64>,
+fn parse_id<T>(obj: &Objects) -> Result<T, ProtocolError>
+where
+    T: FromStr<Err = ParseIntError>,
+{
+    let id = obj.id.as_ref().ok_or_else(
+        || ProtocolError::MissingId(obj.t.clone()),
+    )?;
+    id.parse().map_err(|error| ProtocolError::BadId(id.clone(), error))
 }
 
-fn read_json<T>() -> Option<T>
+fn read_json<T, R>(input: &mut R) -> Result<Option<T>, ProtocolError>
 where
     T: DeserializeOwned,
+    R: BufRead,
 {
-    let value = serde_json::from_str(&read_line()?).expect("failed to parse JSON");
-    serde_json::from_value(value).expect("failed to deserialize")
+    match read_line(input)? {
+        Some(line) => Ok(Some(serde_json::from_str(&line)?)),
+        None => Ok(None),
+    }
+}
+
+fn read_message<R: BufRead>(input: &mut R) -> Result<Option<Message>, ProtocolError> {
+    match read_line(input)? {
+        Some(line) => Ok(Some(Message::parse(&line)?)),
+        None => Ok(None),
+    }
 }
 
-fn read_line() -> Option<String> {
+fn read_line<R: BufRead>(input: &mut R) -> Result<Option<String>, ProtocolError> {
     let mut line = String::new();
-    let n = io::stdin().read_line(&mut line).expect(
-        "failed to read line",
-    );
-    if n == 0 { None } else { Some(line) }
+    let n = input.read_line(&mut line)?;
+    Ok(if n == 0 { None } else { Some(line) })
 }
 
-fn print_command(command: Command) {
-    let response = Response {
-        x: command.point().x,
-        y: command.point().y,
-        debug: command.debug_messages().join("; "),
-        split: command.split(),
-        #[cfg(feature = "debug")]
-        pause: command.pause(),
-        #[cfg(feature = "debug")]
-        draw: Draw {
-            lines: command
-                .debug_lines()
-                .iter()
-                .map(|line| {
-                    DrawLine {
-                        p: vec![XY::from(line.a), XY::from(line.b)],
-                        c: line.color.clone(),
-                        a: line.opacity,
-                    }
-                })
-                .collect(),
-            circles: command
-                .debug_circles()
-                .iter()
-                .map(|circle| {
-                    DrawCircle {
-                        x: circle.center.x,
-                        y: circle.center.y,
-                        r: circle.radius,
-                        c: circle.color.clone(),
-                        a: circle.opacity,
-                    }
-                })
-                .collect(),
-        },
-    };
-    println!(
-        "{}",
-        serde_json::to_string(&response).expect("failed to serialize response")
+fn safe_response(config: &Config) -> Response {
+    let center = Point::new(
+        config.game_width as f64 / 2.0,
+        config.game_height as f64 / 2.0,
     );
+    Response::from_command(&Command::from_point(center, config))
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct Response {
-    x: f64,
-    y: f64,
-    debug: String,
-    split: bool,
-    #[cfg(feature = "debug")]
-    pause: bool,
-    #[cfg(feature = "debug")]
-    draw: Draw,
+fn report_error(error: &ProtocolError) {
+    eprintln!("protocol error: {}", error);
 }
 
-#[cfg(feature = "debug")]
-#[derive(Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct Draw {
-    lines: Vec<DrawLine>,
-    circles: Vec<DrawCircle>,
+fn print_response<W: Write>(output: &mut W, response: &Response) {
+    writeln!(
+        output,
+        "{}",
+        serde_json::to_string(response).expect("failed to serialize response")
+    ).and_then(|_| output.flush())
+        .expect("failed to write response");
 }
 
-#[cfg(feature = "debug")]
-#[derive(Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct DrawLine {
-    p: Vec<XY>,
-    c: String,
-    a: f64,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-#[cfg(feature = "debug")]
-#[derive(Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct XY {
-    x: f64,
-    y: f64,
-}
+    #[derive(Default)]
+    struct RecordingStrategy {
+        ticks: Vec<(Tick, Vec<PlayerBlobId>, usize)>,
+        summaries: Vec<GameSummary>,
+        notices: Vec<String>,
+        game_ended: bool,
+    }
 
-#[cfg(feature = "debug")]
-impl XY {
-    fn from(point: Point) -> XY {
-        XY {
-            x: point.x,
-            y: point.y,
+    impl Strategy for RecordingStrategy {
+        fn tick(&mut self, world: &World) -> Command {
+            let my_ids = world.my_blobs.iter().map(|me| me.id()).collect();
+            let objects = world.food.len() + world.ejections.len() + world.viruses.len() +
+                world.enemies.len();
+            self.ticks.push((world.tick, my_ids, objects));
+            Command::from_point(Point::new(world.tick as f64 + 1.0, 1.0), world.config)
+        }
+
+        fn on_game_over(&mut self, summary: &GameSummary) {
+            self.summaries.push(summary.clone());
+        }
+
+        fn on_notice(&mut self, notice: &Notice) {
+            self.notices.push(notice.kind.clone());
+        }
+
+        fn on_game_end(&mut self) {
+            self.game_ended = true;
         }
     }
-}
 
-#[cfg(feature = "debug")]
-#[derive(Serialize)]
-#[serde(rename_all = "PascalCase")]
-struct DrawCircle {
-    x: f64,
-    y: f64,
-    r: f64,
-    c: String,
-    a: f64,
-}
+    const TICK: &str = r#"{"Mine":[{"Id":"1","X":100,"Y":100,"R":10,"M":40,"SX":0,"SY":0}],"Objects":[]}"#;
+
+    fn play_lines(lines: &[&str]) -> (RecordingStrategy, Vec<Response>) {
+        let config = Config::default();
+        let mut strategy = RecordingStrategy::default();
+        let input = lines.iter().map(|line| format!("{}\n", line)).collect::<String>();
+        let mut output = vec![];
+        play(&mut strategy, &config, &mut input.as_bytes(), &mut output);
+        let responses = String::from_utf8(output)
+            .expect("response is not UTF-8")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("malformed response"))
+            .collect();
+        (strategy, responses)
+    }
+
+    fn point(response: &Response) -> (f64, f64) {
+        (response.x, response.y)
+    }
+
+    #[test]
+    fn answers_unreadable_first_tick_with_safe_response() {
+        let (strategy, responses) = play_lines(&["{not json", TICK]);
+        let config = Config::default();
+        let center = (config.game_width as f64 / 2.0, config.game_height as f64 / 2.0);
+        assert_eq!(responses.len(), 2);
+        assert_eq!(point(&responses[0]), center);
+        assert_eq!(point(&responses[1]), (2.0, 1.0));
+        assert_eq!(strategy.ticks.len(), 1);
+        assert_eq!(strategy.ticks[0].0, 1);
+        assert!(strategy.game_ended);
+    }
 
-impl FromStr for PlayerBlobId {
-    type Err = ParseIntError;
+    #[test]
+    fn replays_last_data_on_malformed_line() {
+        let (strategy, responses) = play_lines(&[TICK, r#"{"Mine":"#, r#"{"Objects":[]}"#]);
+        let responses: Vec<_> = responses.iter().map(point).collect();
+        assert_eq!(responses, vec![(1.0, 1.0), (2.0, 1.0), (3.0, 1.0)]);
+        let my_id = PlayerBlobId {
+            player_id: 1,
+            fragment_id: 0,
+        };
+        let ticks: Vec<_> = strategy
+            .ticks
+            .iter()
+            .map(|&(tick, ref my_ids, _)| (tick, my_ids.clone()))
+            .collect();
+        assert_eq!(
+            ticks,
+            vec![(0, vec![my_id]), (1, vec![my_id]), (2, vec![my_id])]
+        );
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split('.');
-        let player_id = parts.next().expect("no player id").parse()?;
-        let fragment_id = match parts.next() {
-            Some(s) => s.parse()?,
-            None => 0,
+    #[test]
+    fn skips_objects_that_can_not_be_read() {
+        let tick = r#"{"Mine":[
+            {"Id":"1.x","X":10,"Y":10,"R":10,"M":40,"SX":0,"SY":0},
+            {"Id":"1.1","X":100,"Y":100,"R":10,"M":40,"SX":0,"SY":0}
+        ],"Objects":[
+            {"T":"X","X":1,"Y":1},
+            {"T":"E","X":1,"Y":1},
+            {"Id":"3","T":"V","X":1,"Y":1},
+            {"Id":"2","T":"P","X":1,"Y":1,"R":10},
+            {"Id":"2.x","T":"P","X":1,"Y":1,"M":40,"R":10},
+            {"T":"F","X":1,"Y":1}
+        ]}"#.replace('\n', "");
+        let (strategy, responses) = play_lines(&[&tick]);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(point(&responses[0]), (1.0, 1.0));
+        let my_id = PlayerBlobId {
+            player_id: 1,
+            fragment_id: 1,
         };
-        Ok(PlayerBlobId {
-            player_id,
-            fragment_id,
-        })
+        assert_eq!(strategy.ticks, vec![(0, vec![my_id], 1)]);
+    }
+
+    #[test]
+    fn stops_on_game_over() {
+        let (strategy, responses) = play_lines(&[TICK, r#"{"Scores":{"1":10}}"#, TICK]);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(strategy.summaries.len(), 1);
+        assert_eq!(strategy.summaries[0].scores.get("1"), Some(&10));
+        assert!(strategy.game_ended);
+    }
+
+    #[test]
+    fn stops_on_kill_notice() {
+        let (strategy, responses) = play_lines(&[TICK, r#"{"Kind":"kill"}"#, TICK]);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(strategy.notices, vec![String::from(KILL_NOTICE)]);
+        assert!(strategy.game_ended);
+    }
+
+    #[test]
+    fn keeps_playing_after_other_notices() {
+        let (strategy, responses) = play_lines(&[TICK, r#"{"Kind":"warning"}"#, TICK]);
+        let responses: Vec<_> = responses.iter().map(point).collect();
+        assert_eq!(responses, vec![(1.0, 1.0), (2.0, 1.0)]);
+        assert_eq!(strategy.notices, vec![String::from("warning")]);
+        let ticks: Vec<_> = strategy.ticks.iter().map(|&(tick, _, _)| tick).collect();
+        assert_eq!(ticks, vec![0, 1]);
+    }
+
+    #[test]
+    fn stops_at_end_of_input() {
+        let (strategy, responses) = play_lines(&[]);
+        assert!(responses.is_empty());
+        assert!(strategy.ticks.is_empty());
+        assert!(strategy.game_ended);
     }
 }
@@ -13,5 +13,16 @@ pub mod command;
 pub mod config;
 pub mod interactor;
 pub mod models;
+pub mod protocol;
+#[cfg(feature = "tools")]
+pub mod replay;
+#[cfg(feature = "tools")]
+pub mod replay_diff;
+#[cfg(feature = "tools")]
+pub mod server;
+#[cfg(feature = "tools")]
+pub mod simulation_check;
 pub mod strategy;
+#[cfg(feature = "tools")]
+pub mod tuner;
 pub mod version;
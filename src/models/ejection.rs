@@ -1,9 +1,12 @@
 use models::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Ejection {
     pub id_: EjectionId,
     pub point_: Point,
+    pub r_: f64,
+    pub m_: Mass,
+    pub v_: Point,
 }
 
 pub type EjectionId = u64;
@@ -16,7 +19,7 @@ impl HasPoint for Ejection {
 
 impl Circle for Ejection {
     fn r(&self) -> f64 {
-        config().ejection_radius
+        self.r_
     }
 }
 
@@ -28,6 +31,20 @@ impl Blob for Ejection {
     }
 
     fn m(&self) -> Mass {
-        config().ejection_mass
+        self.m_
+    }
+}
+
+impl Ejection {
+    pub fn set_point(&mut self, point: Point) {
+        self.point_ = point;
+    }
+
+    pub fn v(&self) -> Point {
+        self.v_
+    }
+
+    pub fn set_v(&mut self, v: Point) {
+        self.v_ = v;
     }
 }
@@ -1,9 +1,11 @@
 use models::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Food {
     pub id_: FoodId,
     pub point_: Point,
+    pub r_: f64,
+    pub m_: Mass,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -20,7 +22,7 @@ impl HasPoint for Food {
 
 impl Circle for Food {
     fn r(&self) -> f64 {
-        config().food_radius
+        self.r_
     }
 }
 
@@ -32,6 +34,6 @@ impl Blob for Food {
     }
 
     fn m(&self) -> Mass {
-        config().food_mass
+        self.m_
     }
 }
@@ -5,7 +5,7 @@ pub use self::player::{Player, PlayerBlobId, PlayerId, FragmentId};
 pub use self::point::{Point, HasPoint};
 pub use self::virus::{Virus, VirusId};
 
-use config::config;
+use config::Config;
 
 mod common;
 mod ejection;
@@ -45,21 +45,47 @@ impl Blob for Player {
 }
 
 impl Player {
-    pub fn can_eat_player(&self, other: &Player) -> bool {
-        self.player_id() != other.player_id() && self.can_eat_blob(other)
+    pub fn new(
+        player_id: PlayerId,
+        fragment_id: FragmentId,
+        point: Point,
+        m: Mass,
+        config: &Config,
+    ) -> Player {
+        Player {
+            id_: PlayerBlobId {
+                player_id,
+                fragment_id,
+            },
+            point_: point,
+            m_: m,
+            r_: Player::mass_to_radius(m, config),
+            v_: Point::zero(),
+            is_fast_: false,
+            ttf_: 0,
+        }
+    }
+
+    pub fn can_eat_player(&self, other: &Player, config: &Config) -> bool {
+        self.player_id() != other.player_id() && self.can_eat_blob(other, config)
     }
 
-    pub fn can_eat_blob<F: Blob>(&self, food: &F) -> bool {
-        if !(self.m() > food.m() * config().mass_eat_factor) {
+    pub fn can_eat_blob<F: Blob>(&self, food: &F, config: &Config) -> bool {
+        if !(self.m() > food.m() * config.mass_eat_factor) {
             return false;
         }
         let qdist = self.point().qdist(food.point());
-        let max_dist = self.r() + food.r() - food.r() * 2.0 * config().diam_eat_factor;
+        let max_dist = self.r() + food.r() - food.r() * 2.0 * config.diam_eat_factor;
         max_dist > 0.0 && qdist < max_dist.powi(2)
     }
 
-    pub fn can_see<Other: Circle>(&self, other: &Other, fragment_count: usize) -> bool {
-        self.can_see_safe(other, fragment_count, 1.0)
+    pub fn can_see<Other: Circle>(
+        &self,
+        other: &Other,
+        fragment_count: usize,
+        config: &Config,
+    ) -> bool {
+        self.can_see_safe(other, fragment_count, 1.0, config)
     }
 
     pub fn can_see_safe<Other: Circle>(
@@ -67,9 +93,10 @@ impl Player {
         other: &Other,
         fragment_count: usize,
         safety_factor: f64,
+        config: &Config,
     ) -> bool {
-        let vision_center = self.point() + Point::from_polar(config().vis_shift, self.angle());
-        let max_dist = self.vision_radius(fragment_count) * safety_factor + other.r();
+        let vision_center = self.point() + Point::from_polar(config.vis_shift, self.angle());
+        let max_dist = self.vision_radius(fragment_count, config) * safety_factor + other.r();
         other.point().qdist(vision_center) < max_dist.powi(2)
     }
 
@@ -78,31 +105,35 @@ impl Player {
             self.point().qdist(other.point()) <= (self.r() + other.r()).powi(2)
     }
 
-    pub fn can_burst(&self, yet_cnt: i64) -> bool {
-        if self.m() < config().min_burst_mass * 2.0 || Player::rest_fragment_count(yet_cnt) <= 0 {
+    pub fn can_burst(&self, yet_cnt: i64, config: &Config) -> bool {
+        if self.m() < config.min_burst_mass * 2.0 || Player::rest_fragment_count(yet_cnt, config) <= 0 {
             return false;
         }
-        let frags_cnt = (self.m() / config().min_burst_mass).floor() as i64;
+        let frags_cnt = (self.m() / config.min_burst_mass).floor() as i64;
         frags_cnt > 1
     }
 
-    pub fn can_split(&self, yet_cnt: i64) -> bool {
-        Player::rest_fragment_count(yet_cnt) > 0 && self.m() > config().min_split_mass
+    pub fn can_eject(&self, config: &Config) -> bool {
+        self.m() > config.min_ejection_mass
+    }
+
+    pub fn can_split(&self, yet_cnt: i64, config: &Config) -> bool {
+        Player::rest_fragment_count(yet_cnt, config) > 0 && self.m() > config.min_split_mass
     }
 
-    pub fn max_speed(&self) -> Speed {
-        config().speed_factor / self.m().sqrt()
+    pub fn max_speed(&self, config: &Config) -> Speed {
+        config.speed_factor / self.m().sqrt()
     }
 
-    pub fn can_shrink(&self) -> bool {
-        self.m() > config().min_shrink_mass
+    pub fn can_shrink(&self, config: &Config) -> bool {
+        self.m() > config.min_shrink_mass
     }
 
-    pub fn vision_radius(&self, fragment_count: usize) -> f64 {
+    pub fn vision_radius(&self, fragment_count: usize, config: &Config) -> f64 {
         if fragment_count == 1 {
-            self.r() * config().vis_factor
+            self.r() * config.vis_factor
         } else {
-            self.r() * config().vis_factor_fr * (fragment_count as f64).sqrt()
+            self.r() * config.vis_factor_fr * (fragment_count as f64).sqrt()
         }
     }
 
@@ -134,8 +165,8 @@ impl Player {
         self.is_fast_ = is_fast;
     }
 
-    pub fn update_is_fast(&mut self) {
-        self.is_fast_ = self.speed() > self.max_speed();
+    pub fn update_is_fast(&mut self, config: &Config) {
+        self.is_fast_ = self.speed() > self.max_speed(config);
     }
 
     pub fn set_m(&mut self, m: Mass) {
@@ -170,11 +201,11 @@ impl Player {
         self.id_.fragment_id = fragment_id;
     }
 
-    pub fn rest_fragment_count(existing_fragment_count: i64) -> i64 {
-        config().max_frags_cnt - existing_fragment_count
+    pub fn rest_fragment_count(existing_fragment_count: i64, config: &Config) -> i64 {
+        config.max_frags_cnt - existing_fragment_count
     }
 
-    pub fn mass_to_radius(mass: Mass) -> f64 {
-        config().radius_factor * mass.sqrt()
+    pub fn mass_to_radius(mass: Mass, config: &Config) -> f64 {
+        config.radius_factor * mass.sqrt()
     }
 }
@@ -1,10 +1,13 @@
 use models::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Virus {
     pub id_: VirusId,
     pub point_: Point,
+    pub r_: f64,
     pub m_: Mass,
+    pub v_: Point,
+    pub split_angle_: Angle,
 }
 
 pub type VirusId = u64;
@@ -17,7 +20,7 @@ impl HasPoint for Virus {
 
 impl Circle for Virus {
     fn r(&self) -> f64 {
-        config().virus_radius
+        self.r_
     }
 }
 
@@ -34,11 +37,43 @@ impl Blob for Virus {
 }
 
 impl Virus {
-    pub fn can_hurt(&self, other: &Player) -> bool {
+    pub fn can_eat_ejection(&self, ejection: &Ejection) -> bool {
+        self.point().qdist(ejection.point()) < (self.r() + ejection.r()).powi(2)
+    }
+
+    pub fn can_split(&self, config: &Config) -> bool {
+        self.m() > config.virus_split_mass
+    }
+
+    pub fn set_point(&mut self, point: Point) {
+        self.point_ = point;
+    }
+
+    pub fn v(&self) -> Point {
+        self.v_
+    }
+
+    pub fn set_v(&mut self, v: Point) {
+        self.v_ = v;
+    }
+
+    pub fn set_m(&mut self, m: Mass) {
+        self.m_ = m;
+    }
+
+    pub fn split_angle(&self) -> Angle {
+        self.split_angle_
+    }
+
+    pub fn set_split_angle(&mut self, split_angle: Angle) {
+        self.split_angle_ = split_angle;
+    }
+
+    pub fn can_hurt(&self, other: &Player, config: &Config) -> bool {
         if other.r() < self.r() {
             return false;
         }
-        let max_dist = self.r() * config().rad_hurt_factor + other.r();
+        let max_dist = self.r() * config.rad_hurt_factor + other.r();
         self.point().qdist(other.point()) < max_dist.powi(2)
     }
 }
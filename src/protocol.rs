@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use serde_json;
+
+use command::*;
+use config::Config;
+use models::*;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Message {
+    Tick(TickData),
+    GameOver(GameSummary),
+    Notice(Notice),
+}
+
+impl Message {
+    // Tries the shapes one by one instead of deriving an untagged Deserialize, so that a malformed
+    // tick reports its TickData error rather than matching no variant.
+    pub fn parse(line: &str) -> Result<Message, serde_json::Error> {
+        let error = match serde_json::from_str(line) {
+            Ok(data) => return Ok(Message::Tick(data)),
+            Err(error) => error,
+        };
+        if let Ok(summary) = serde_json::from_str(line) {
+            return Ok(Message::GameOver(summary));
+        }
+        if let Ok(notice) = serde_json::from_str(line) {
+            return Ok(Message::Notice(notice));
+        }
+        Err(error)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct GameSummary {
+    pub scores: BTreeMap<String, i64>,
+}
+
+pub const KILL_NOTICE: &str = "kill";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Notice {
+    pub kind: String,
+    #[serde(default)]
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TickData {
+    pub mine: Vec<Mine>,
+    pub objects: Vec<Objects>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Mine {
+    pub id: String,
+    pub x: f64,
+    pub y: f64,
+    pub r: f64,
+    pub m: f64,
+    pub s_x: f64,
+    pub s_y: f64,
+    #[serde(rename = "TTF")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttf: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Objects {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub t: String,
+    pub x: f64,
+    pub y: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub m: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Response {
+    pub x: f64,
+    pub y: f64,
+    #[serde(default)]
+    pub debug: String,
+    #[serde(default)]
+    pub split: bool,
+    #[serde(default)]
+    pub eject: bool,
+    #[cfg(feature = "debug")]
+    #[serde(default)]
+    pub pause: bool,
+    #[cfg(feature = "debug")]
+    #[serde(default)]
+    pub draw: Draw,
+}
+
+impl Response {
+    pub fn from_command(command: &Command) -> Response {
+        Response {
+            x: command.point().x,
+            y: command.point().y,
+            debug: command.debug_messages().join("; "),
+            split: command.split(),
+            eject: command.eject(),
+            #[cfg(feature = "debug")]
+            pause: command.pause(),
+            #[cfg(feature = "debug")]
+            draw: Draw {
+                lines: command
+                    .debug_lines()
+                    .iter()
+                    .map(|line| {
+                        DrawLine {
+                            p: vec![XY::from(line.a), XY::from(line.b)],
+                            c: line.color.clone(),
+                            a: line.opacity,
+                        }
+                    })
+                    .collect(),
+                circles: command
+                    .debug_circles()
+                    .iter()
+                    .map(|circle| {
+                        DrawCircle {
+                            x: circle.center.x,
+                            y: circle.center.y,
+                            r: circle.radius,
+                            c: circle.color.clone(),
+                            a: circle.opacity,
+                        }
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    pub fn to_command(&self, config: &Config) -> Command {
+        let mut command = Command::from_point(Point::new(self.x, self.y), config);
+        if self.split {
+            command.set_split();
+        }
+        if self.eject {
+            command.set_eject();
+        }
+        command
+    }
+}
+
+#[cfg(feature = "debug")]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Draw {
+    pub lines: Vec<DrawLine>,
+    pub circles: Vec<DrawCircle>,
+}
+
+#[cfg(feature = "debug")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DrawLine {
+    pub p: Vec<XY>,
+    pub c: String,
+    pub a: f64,
+}
+
+#[cfg(feature = "debug")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct XY {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[cfg(feature = "debug")]
+impl XY {
+    pub fn from(point: Point) -> XY {
+        XY {
+            x: point.x,
+            y: point.y,
+        }
+    }
+}
+
+#[cfg(feature = "debug")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DrawCircle {
+    pub x: f64,
+    pub y: f64,
+    pub r: f64,
+    pub c: String,
+    pub a: f64,
+}
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    Io(io::Error),
+    MalformedJson(serde_json::Error),
+    UnknownObjectType(String),
+    MissingId(String),
+    MissingMass(String),
+    MissingRadius(String),
+    BadId(String, ParseIntError),
+}
+
+impl From<io::Error> for ProtocolError {
+    fn from(error: io::Error) -> ProtocolError {
+        ProtocolError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ProtocolError {
+    fn from(error: serde_json::Error) -> ProtocolError {
+        ProtocolError::MalformedJson(error)
+    }
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProtocolError::Io(ref error) => write!(f, "failed to read line: {}", error),
+            ProtocolError::MalformedJson(ref error) => write!(f, "malformed JSON: {}", error),
+            ProtocolError::UnknownObjectType(ref t) => write!(f, "unknown object type {:?}", t),
+            ProtocolError::MissingId(ref t) => write!(f, "object of type {:?} has no id", t),
+            ProtocolError::MissingMass(ref t) => write!(f, "object of type {:?} has no mass", t),
+            ProtocolError::MissingRadius(ref t) => {
+                write!(f, "object of type {:?} has no radius", t)
+            }
+            ProtocolError::BadId(ref id, ref error) => {
+                write!(f, "failed to parse id {:?}: {}", id, error)
+            }
+        }
+    }
+}
+
+impl FromStr for PlayerBlobId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('.');
+        let player_id = parts.next().expect("no player id").parse()?;
+        let fragment_id = match parts.next() {
+            Some(s) => s.parse()?,
+            None => 0,
+        };
+        Ok(PlayerBlobId {
+            player_id,
+            fragment_id,
+        })
+    }
+}
+
+impl fmt::Display for PlayerBlobId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.fragment_id == 0 {
+            write!(f, "{}", self.player_id)
+        } else {
+            write!(f, "{}.{}", self.player_id, self.fragment_id)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tick_data() {
+        let line = r#"{"Mine":[{"Id":"1.2","X":1,"Y":2,"R":3,"M":4,"SX":0,"SY":0}],"Objects":[]}"#;
+        match Message::parse(line) {
+            Ok(Message::Tick(data)) => {
+                assert_eq!(data.mine.len(), 1);
+                assert_eq!(data.mine[0].id, "1.2");
+            }
+            other => panic!("expected tick data, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_game_summary() {
+        match Message::parse(r#"{"Scores":{"1":10,"2":20}}"#) {
+            Ok(Message::GameOver(summary)) => {
+                assert_eq!(summary.scores.get("2"), Some(&20));
+            }
+            other => panic!("expected a game summary, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_notice() {
+        match Message::parse(r#"{"Kind":"kill","Message":"too slow"}"#) {
+            Ok(Message::Notice(notice)) => {
+                assert_eq!(notice.kind, KILL_NOTICE);
+                assert_eq!(notice.message, "too slow");
+            }
+            other => panic!("expected a notice, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reports_tick_data_field_errors() {
+        let error = Message::parse(r#"{"Mine":"none","Objects":[]}"#)
+            .expect_err("malformed tick parsed");
+        assert!(error.to_string().contains("expected a sequence"));
+    }
+}
@@ -0,0 +1,158 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use serde_json;
+
+use config::Config;
+use protocol::*;
+use strategy::Tick;
+
+pub const REPLAY_VERSION: i64 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    Header { version: i64, config: Config },
+    Tick(ReplayTick),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayTick {
+    pub tick: Tick,
+    pub data: TickData,
+    pub response: Response,
+    pub elapsed_secs: f64,
+    // Set when the tick could not be read, so the strategy never saw it.
+    #[serde(default)]
+    pub safe_response: bool,
+}
+
+#[derive(Debug)]
+pub struct Replay {
+    pub version: i64,
+    pub config: Config,
+    pub ticks: Vec<ReplayTick>,
+}
+
+impl Replay {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Replay> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut header = None;
+        let mut ticks = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line).map_err(invalid_data)? {
+                Record::Header { version, config } => {
+                    if version != REPLAY_VERSION {
+                        return Err(invalid_data(
+                            format!("unsupported replay version {}", version),
+                        ));
+                    }
+                    header = Some((version, config));
+                }
+                Record::Tick(tick) => ticks.push(tick),
+            }
+        }
+        let (version, config) = header.ok_or_else(|| invalid_data("replay has no header"))?;
+        Ok(Replay {
+            version,
+            config,
+            ticks,
+        })
+    }
+}
+
+pub struct ReplayWriter {
+    writer: BufWriter<File>,
+}
+
+impl ReplayWriter {
+    pub fn create<P: AsRef<Path>>(path: P, config: &Config) -> io::Result<ReplayWriter> {
+        let mut replay = ReplayWriter { writer: BufWriter::new(File::create(path)?) };
+        replay.write(&Record::Header {
+            version: REPLAY_VERSION,
+            config: config.clone(),
+        })?;
+        Ok(replay)
+    }
+
+    pub fn write_tick(
+        &mut self,
+        tick: Tick,
+        data: TickData,
+        response: Response,
+        elapsed: Duration,
+    ) -> io::Result<()> {
+        self.write(&Record::Tick(ReplayTick {
+            tick,
+            data,
+            response,
+            elapsed_secs: elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9,
+            safe_response: false,
+        }))
+    }
+
+    pub fn write_safe_response(&mut self, tick: Tick, response: Response) -> io::Result<()> {
+        self.write(&Record::Tick(ReplayTick {
+            tick,
+            data: Default::default(),
+            response,
+            elapsed_secs: 0.0,
+            safe_response: true,
+        }))
+    }
+
+    fn write(&mut self, record: &Record) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "{}",
+            serde_json::to_string(record).map_err(invalid_data)?
+        )?;
+        self.writer.flush()
+    }
+}
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::*;
+    use command::Command;
+    use models::Point;
+
+    #[test]
+    fn records_safe_responses() {
+        let path = env::temp_dir().join(format!("replay-test-{}.jsonl", ::std::process::id()));
+        let config = Config::default();
+        let response = Response::from_command(&Command::from_point(Point::new(1.0, 2.0), &config));
+        {
+            let mut writer = ReplayWriter::create(&path, &config).expect("failed to create replay");
+            writer.write_safe_response(0, response.clone()).expect("failed to write");
+            writer
+                .write_tick(1, Default::default(), response, Duration::from_millis(1))
+                .expect("failed to write");
+        }
+        let replay = Replay::load(&path).expect("failed to load replay");
+        fs::remove_file(&path).expect("failed to remove replay");
+        let ticks: Vec<_> = replay
+            .ticks
+            .iter()
+            .map(|tick| (tick.tick, tick.safe_response))
+            .collect();
+        assert_eq!(ticks, vec![(0, true), (1, false)]);
+    }
+}
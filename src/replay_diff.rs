@@ -0,0 +1,100 @@
+use std::env;
+use std::process;
+
+use config::Config;
+use interactor::read_world;
+use models::*;
+use protocol::Response;
+use replay::{Replay, ReplayTick};
+use strategy::*;
+
+const DEFAULT_MAX_PATHS: usize = 64;
+const POINT_EPSILON: f64 = 1e-6;
+
+#[derive(Debug)]
+pub struct CommandDiff {
+    pub tick: Tick,
+    pub recorded: Response,
+    pub produced: Response,
+}
+
+impl CommandDiff {
+    pub fn point_delta(&self) -> Point {
+        Point::new(
+            self.produced.x - self.recorded.x,
+            self.produced.y - self.recorded.y,
+        )
+    }
+}
+
+pub fn run() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() || args.len() > 2 {
+        eprintln!("usage: replay_diff REPLAY [MAX_PATHS]");
+        process::exit(2);
+    }
+    let replay = Replay::load(&args[0]).expect("failed to load replay");
+    let max_paths = args.get(1).map_or(DEFAULT_MAX_PATHS, |arg| {
+        arg.parse().expect("MAX_PATHS is not a number")
+    });
+    let mut strategy = MyStrategy::new();
+    strategy.set_budget(Budget::Paths(max_paths));
+    strategy.on_start(&replay.config);
+    let diffs = diff_commands(&replay.ticks, &replay.config, &mut strategy);
+    strategy.on_game_end();
+
+    println!("tick\tdx\tdy\tdist\tsplit\teject");
+    for diff in diffs.iter() {
+        let delta = diff.point_delta();
+        println!(
+            "{}\t{:.3}\t{:.3}\t{:.3}\t{}\t{}",
+            diff.tick,
+            delta.x,
+            delta.y,
+            delta.length(),
+            mismatch(diff.recorded.split, diff.produced.split),
+            mismatch(diff.recorded.eject, diff.produced.eject)
+        );
+    }
+    println!(
+        "{} of {} ticks differ{}",
+        diffs.len(),
+        replay.ticks.len(),
+        diffs.first().map_or(String::new(), |diff| {
+            format!(", first at tick {}", diff.tick)
+        })
+    );
+}
+
+pub fn diff_commands<S: Strategy>(
+    ticks: &[ReplayTick],
+    config: &Config,
+    strategy: &mut S,
+) -> Vec<CommandDiff> {
+    let mut diffs = vec![];
+    for replay_tick in ticks.iter().filter(|replay_tick| !replay_tick.safe_response) {
+        let world = read_world(replay_tick.tick, &replay_tick.data, config);
+        let command = strategy.tick(&world);
+        let produced = Response::from_command(&command);
+        let recorded = &replay_tick.response;
+        if (produced.x - recorded.x).abs() > POINT_EPSILON ||
+            (produced.y - recorded.y).abs() > POINT_EPSILON ||
+            produced.split != recorded.split || produced.eject != recorded.eject
+        {
+            diffs.push(CommandDiff {
+                tick: replay_tick.tick,
+                recorded: recorded.clone(),
+                produced,
+            });
+        }
+    }
+    diffs
+}
+
+fn mismatch(recorded: bool, produced: bool) -> String {
+    if recorded == produced {
+        String::from("-")
+    } else {
+        format!("{}->{}", recorded, produced)
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use config::Config;
+use protocol::*;
+use strategy::*;
+use strategy::grid::Grid;
+use strategy::mechanic::{Mechanic, Points, State};
+
+pub const PLAYER_COUNT: PlayerId = 4;
+
+const START_PLAYER_MASS: Mass = 40.0;
+const START_FOOD_SETS: usize = 4;
+const ADD_FOOD_SETS: usize = 2;
+const ADD_FOOD_DELAY: Tick = 40;
+const MAX_GAME_FOOD: usize = 2000;
+const START_VIRUS_SETS: usize = 1;
+const ADD_VIRUS_DELAY: Tick = 1200;
+const MAX_GAME_VIRUS: usize = 20;
+const MAX_VIRUS_PLACEMENT_ATTEMPTS: usize = 100;
+const SCORE_FOR_LAST: Points = 100;
+
+#[derive(Debug)]
+pub struct Game<'c> {
+    mechanic: Mechanic<'c>,
+    food: Vec<Food>,
+    food_grid: Grid,
+    last_commands: HashMap<PlayerId, Command>,
+    rng: XorShiftRng,
+}
+
+impl<'c> Game<'c> {
+    pub fn new(seed: u32, config: &'c Config) -> Game<'c> {
+        let mut mechanic = Mechanic::with_scores(&State::default(), config);
+        mechanic.set_predict_enemies(false);
+        let mut game = Game {
+            mechanic,
+            food: Default::default(),
+            food_grid: Grid::for_config(config),
+            last_commands: Default::default(),
+            rng: XorShiftRng::from_seed([seed | 1, 0x1337_5EED, seed ^ 0xA6A6, 0xC0FF_EE00]),
+        };
+        game.add_players();
+        game.add_food(START_FOOD_SETS);
+        game.add_viruses(START_VIRUS_SETS);
+        game.food_grid.rebuild(&game.food);
+        game
+    }
+
+    pub fn config(&self) -> &'c Config {
+        self.mechanic.config()
+    }
+
+    pub fn tick(&self) -> Tick {
+        self.mechanic.state.tick
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.mechanic.state.enemies
+    }
+
+    pub fn is_over(&self, game_ticks: Tick) -> bool {
+        self.tick() >= game_ticks || self.alive_player_ids().len() <= 1
+    }
+
+    pub fn alive_player_ids(&self) -> Vec<PlayerId> {
+        let mut player_ids: Vec<_> = self.players()
+            .iter()
+            .map(|player| player.player_id())
+            .collect();
+        player_ids.sort_unstable();
+        player_ids.dedup();
+        player_ids
+    }
+
+    pub fn scores(&self) -> BTreeMap<PlayerId, Points> {
+        let scores = self.mechanic.scores().expect("game mechanic keeps no scores");
+        let alive_player_ids = self.alive_player_ids();
+        (1..PLAYER_COUNT + 1)
+            .map(|player_id| {
+                let mut points = scores.get(&player_id).cloned().unwrap_or(0);
+                if alive_player_ids == [player_id] {
+                    points += SCORE_FOR_LAST;
+                }
+                (player_id, points)
+            })
+            .collect()
+    }
+
+    // A player that sends no command, e.g. because its client died, keeps heading for its last
+    // target without splitting or ejecting again.
+    pub fn play_tick(&mut self, commands: &HashMap<PlayerId, Command>) {
+        let config = self.config();
+        for command in self.last_commands.values_mut() {
+            *command = Command::from_point(command.point(), config);
+        }
+        for (&player_id, command) in commands.iter() {
+            self.last_commands.insert(player_id, command.clone());
+        }
+        self.mechanic.tick_all(&self.last_commands, &self.food);
+        self.remove_eaten();
+        if self.tick() % ADD_FOOD_DELAY == 0 && self.food.len() < MAX_GAME_FOOD {
+            self.add_food(ADD_FOOD_SETS);
+        }
+        if self.tick() % ADD_VIRUS_DELAY == 0 &&
+            self.mechanic.state.viruses.len() < MAX_GAME_VIRUS
+        {
+            self.add_viruses(1);
+        }
+        self.food_grid.rebuild(&self.food);
+    }
+
+    pub fn tick_data(&self, player_id: PlayerId) -> TickData {
+        let config = self.config();
+        let my_blobs: Vec<_> = self.players()
+            .iter()
+            .filter(|player| player.player_id() == player_id)
+            .collect();
+        fn can_see<C: Circle>(my_blobs: &[&Player], other: &C, config: &Config) -> bool {
+            my_blobs.iter().any(|me| me.can_see(other, my_blobs.len(), config))
+        }
+
+        let mut nearby_food = vec![];
+        let mut found = vec![];
+        for me in my_blobs.iter() {
+            let vision_center = me.point() + Point::from_polar(config.vis_shift, me.angle());
+            self.food_grid.query(
+                vision_center,
+                me.vision_radius(my_blobs.len(), config),
+                &mut found,
+            );
+            nearby_food.extend_from_slice(&found);
+        }
+        nearby_food.sort_unstable();
+        nearby_food.dedup();
+
+        let mut objects = vec![];
+        for food in nearby_food.into_iter().map(|i| &self.food[i]).filter(|food| {
+            can_see(&my_blobs, *food, config)
+        })
+        {
+            objects.push(Objects {
+                id: None,
+                t: String::from("F"),
+                x: food.x(),
+                y: food.y(),
+                m: None,
+                r: None,
+            });
+        }
+        for ejection in self.mechanic.state.ejections.iter().filter(|ejection| {
+            can_see(&my_blobs, *ejection, config)
+        })
+        {
+            objects.push(Objects {
+                id: Some(ejection.id().to_string()),
+                t: String::from("E"),
+                x: ejection.x(),
+                y: ejection.y(),
+                m: None,
+                r: None,
+            });
+        }
+        for virus in self.mechanic.state.viruses.iter().filter(|virus| {
+            can_see(&my_blobs, *virus, config)
+        })
+        {
+            objects.push(Objects {
+                id: Some(virus.id().to_string()),
+                t: String::from("V"),
+                x: virus.x(),
+                y: virus.y(),
+                m: Some(virus.m()),
+                r: None,
+            });
+        }
+        for enemy in self.players().iter().filter(|enemy| {
+            enemy.player_id() != player_id && can_see(&my_blobs, *enemy, config)
+        })
+        {
+            objects.push(Objects {
+                id: Some(enemy.id().to_string()),
+                t: String::from("P"),
+                x: enemy.x(),
+                y: enemy.y(),
+                m: Some(enemy.m()),
+                r: Some(enemy.r()),
+            });
+        }
+
+        TickData {
+            mine: my_blobs
+                .iter()
+                .map(|me| {
+                    Mine {
+                        id: me.id().to_string(),
+                        x: me.x(),
+                        y: me.y(),
+                        r: me.r(),
+                        m: me.m(),
+                        s_x: me.v().x,
+                        s_y: me.v().y,
+                        ttf: if me.ttf() > 0 { Some(me.ttf()) } else { None },
+                    }
+                })
+                .collect(),
+            objects,
+        }
+    }
+
+    fn remove_eaten(&mut self) {
+        let state = &mut self.mechanic.state;
+        self.food.retain(|food| !state.eaten_food.contains(&food.id()));
+        state.eaten_food.clear();
+        state.eaten_ejections.clear();
+        state.eaten_viruses.clear();
+    }
+
+    fn add_players(&mut self) {
+        let r = Player::mass_to_radius(START_PLAYER_MASS, self.config());
+        let points = self.symmetric_points(r);
+        for (i, point) in points.into_iter().enumerate() {
+            self.mechanic.state.enemies.push(Player {
+                id_: PlayerBlobId {
+                    player_id: i as PlayerId + 1,
+                    fragment_id: 0,
+                },
+                point_: point,
+                m_: START_PLAYER_MASS,
+                r_: r,
+                v_: Point::zero(),
+                is_fast_: false,
+                ttf_: 0,
+            });
+        }
+    }
+
+    fn add_food(&mut self, sets: usize) {
+        let config = self.config();
+        for _ in 0..sets {
+            let points = self.symmetric_points(config.food_radius);
+            for point in points {
+                self.food.push(Food {
+                    id_: FoodId {
+                        x10: (point.x * 10.0).floor() as u32,
+                        y10: (point.y * 10.0).floor() as u32,
+                    },
+                    point_: point,
+                    r_: config.food_radius,
+                    m_: config.food_mass,
+                });
+            }
+        }
+    }
+
+    fn add_viruses(&mut self, sets: usize) {
+        let config = self.config();
+        for _ in 0..sets {
+            // Gives up on the set when the map is too crowded to place it.
+            let points = match self.free_virus_points() {
+                Some(points) => points,
+                None => continue,
+            };
+            let max_virus_id = self.mechanic
+                .state
+                .viruses
+                .iter()
+                .map(|virus| virus.id())
+                .max()
+                .unwrap_or(0);
+            for (id, point) in (max_virus_id + 1..).zip(points) {
+                self.mechanic.state.viruses.push(Virus {
+                    id_: id,
+                    point_: point,
+                    r_: config.virus_radius,
+                    m_: config.virus_mass,
+                    v_: Point::zero(),
+                    split_angle_: 0.0,
+                });
+            }
+        }
+    }
+
+    fn free_virus_points(&mut self) -> Option<Vec<Point>> {
+        let config = self.config();
+        for _ in 0..MAX_VIRUS_PLACEMENT_ATTEMPTS {
+            let points = self.symmetric_points(config.virus_radius);
+            let is_free = |point: &Point| {
+                self.players().iter().all(|player| {
+                    point.qdist(player.point()) > (player.r() + config.virus_radius).powi(2)
+                })
+            };
+            if points.iter().all(is_free) {
+                return Some(points);
+            }
+        }
+        None
+    }
+
+    fn symmetric_points(&mut self, margin: f64) -> Vec<Point> {
+        let width = self.config().game_width as f64;
+        let height = self.config().game_height as f64;
+        let x = self.rng.gen_range(margin, width / 2.0);
+        let y = self.rng.gen_range(margin, height / 2.0);
+        vec![
+            Point::new(x, y),
+            Point::new(width - x, y),
+            Point::new(x, height - y),
+            Point::new(width - x, height - y),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::Config;
+
+    #[test]
+    fn silent_players_keep_their_last_command() {
+        let config = Config::default();
+        let mut game = Game::new(0x5EED, &config);
+        let m = 200.0;
+        for player in game.mechanic.state.enemies.iter_mut() {
+            player.set_m(m);
+            player.set_r(Player::mass_to_radius(m, &config));
+        }
+        let corner = Point::new(0.0, 0.0);
+        let mut commands = HashMap::new();
+        for player_id in 1..PLAYER_COUNT + 1 {
+            let mut command = Command::from_point(corner, &config);
+            command.set_split();
+            command.set_eject();
+            commands.insert(player_id, command);
+        }
+        game.play_tick(&commands);
+        let fragments = game.players().len();
+        assert_eq!(fragments, 2 * PLAYER_COUNT as usize);
+        let last_ejection_id = game.mechanic
+            .state
+            .ejections
+            .iter()
+            .map(|ejection| ejection.id())
+            .max()
+            .expect("nobody ejected");
+        for _ in 0..60 {
+            game.play_tick(&HashMap::new());
+        }
+        assert_eq!(game.players().len(), fragments);
+        assert!(game.mechanic.state.ejections.iter().all(|ejection| {
+            ejection.id() <= last_ejection_id
+        }));
+        for player in game.players() {
+            let to_corner = corner - player.point();
+            assert!(player.v().x * to_corner.x + player.v().y * to_corner.y > 0.0);
+            assert!(player.speed() > 0.9 * player.max_speed(&config));
+        }
+    }
+
+    #[test]
+    fn skips_viruses_that_do_not_fit() {
+        let config = Config::default();
+        let mut game = Game::new(0x5EED, &config);
+        let viruses = game.mechanic.state.viruses.len();
+        for player in game.mechanic.state.enemies.iter_mut() {
+            player.set_r(config.game_width as f64);
+        }
+        game.add_viruses(1);
+        assert_eq!(game.mechanic.state.viruses.len(), viruses);
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{self, Child, ChildStdin, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json;
+
+use config::Config;
+use interactor::read_world;
+use protocol::*;
+use strategy::*;
+use strategy::mechanic::Points;
+
+pub use self::game::{Game, PLAYER_COUNT};
+
+mod game;
+
+const DEFAULT_GAME_TICKS: Tick = 7500;
+const DEFAULT_SEED: u32 = 0x5EED;
+const DEFAULT_TICK_TIMEOUT_MS: u64 = 1000;
+const FINISH_TIMEOUT_MS: u64 = 5000;
+const FINISH_POLL_MS: u64 = 10;
+const DEFAULT_SCORES_LOCATION: &str = "/var/tmp/scores.json";
+
+pub fn run() {
+    let strategies: Vec<String> = env::args().skip(1).collect();
+    if strategies.len() != PLAYER_COUNT as usize {
+        eprintln!("usage: local_server STRATEGY STRATEGY STRATEGY STRATEGY");
+        process::exit(2);
+    }
+    let config = Config::default();
+    let game_ticks = env_or("GAME_TICKS", DEFAULT_GAME_TICKS);
+    let seed = env_or("SEED", DEFAULT_SEED);
+    let tick_timeout = Duration::from_millis(env_or("TICK_TIMEOUT_MS", DEFAULT_TICK_TIMEOUT_MS));
+    let scores_location =
+        env::var("SCORES_LOCATION").unwrap_or_else(|_| String::from(DEFAULT_SCORES_LOCATION));
+
+    let config_line = serde_json::to_string(&config).expect("failed to serialize config");
+    let mut clients: Vec<_> = strategies
+        .iter()
+        .map(|strategy| {
+            let mut client = Client::spawn(strategy, tick_timeout);
+            client.send(&config_line);
+            client
+        })
+        .collect();
+
+    let mut game = Game::new(seed, &config);
+    while !game.is_over(game_ticks) {
+        let mut commands = HashMap::new();
+        for (i, client) in clients.iter_mut().enumerate() {
+            let player_id = i as PlayerId + 1;
+            let data = serde_json::to_string(&game.tick_data(player_id))
+                .expect("failed to serialize tick data");
+            client.send(&data);
+            if let Some(response) = client.receive() {
+                commands.insert(player_id, response.to_command(&config));
+            }
+        }
+        game.play_tick(&commands);
+    }
+
+    let scores = game.scores();
+    for (player_id, points) in scores.iter() {
+        eprintln!("{}\t{}\t{}", player_id, points, strategies[*player_id as usize - 1]);
+    }
+    let file = File::create(&scores_location).expect("failed to create scores file");
+    serde_json::to_writer(file, &scores).expect("failed to write scores");
+    let summary = GameSummary {
+        scores: scores
+            .iter()
+            .map(|(player_id, &points)| (player_id.to_string(), points))
+            .collect(),
+    };
+    let summary = serde_json::to_string(&Message::GameOver(summary)).expect(
+        "failed to serialize game summary",
+    );
+    for mut client in clients {
+        client.send(&summary);
+        client.finish();
+    }
+}
+
+pub fn play_headless(
+    seed: u32,
+    game_ticks: Tick,
+    config: &Config,
+    strategies: &mut [Box<dyn Strategy>],
+) -> BTreeMap<PlayerId, Points> {
+    assert_eq!(strategies.len(), PLAYER_COUNT as usize);
+    for strategy in strategies.iter_mut() {
+        strategy.on_start(config);
+    }
+    let mut game = Game::new(seed, config);
+    while !game.is_over(game_ticks) {
+        let mut commands = HashMap::new();
+        for (i, strategy) in strategies.iter_mut().enumerate() {
+            let player_id = i as PlayerId + 1;
+            let world = read_world(game.tick(), &game.tick_data(player_id), config);
+            let command = strategy.tick(&world);
+            commands.insert(
+                player_id,
+                Response::from_command(&command).to_command(config),
+            );
+        }
+        game.play_tick(&commands);
+    }
+    for strategy in strategies.iter_mut() {
+        strategy.on_game_end();
+    }
+    game.scores()
+}
+
+struct Client {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    lines: Receiver<String>,
+    timeout: Duration,
+    alive: bool,
+}
+
+impl Client {
+    fn spawn(command: &str, timeout: Duration) -> Client {
+        let mut child = process::Command::new(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|error| panic!("failed to spawn strategy {:?}: {}", command, error));
+        let stdin = child.stdin.take();
+        let stdout = BufReader::new(child.stdout.take().expect("strategy has no stdout"));
+        // Reads on a separate thread, so a stuck strategy can't block the game.
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || for line in stdout.lines() {
+            match line {
+                Ok(line) => {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        });
+        Client {
+            child,
+            stdin,
+            lines,
+            timeout,
+            alive: true,
+        }
+    }
+
+    fn send(&mut self, line: &str) {
+        if !self.alive {
+            return;
+        }
+        let result = match self.stdin {
+            Some(ref mut stdin) => writeln!(stdin, "{}", line).and_then(|_| stdin.flush()),
+            None => Err(io::Error::new(io::ErrorKind::BrokenPipe, "stdin is closed")),
+        };
+        if result.is_err() {
+            self.alive = false;
+        }
+    }
+
+    fn receive(&mut self) -> Option<Response> {
+        if !self.alive {
+            return None;
+        }
+        let line = match self.lines.recv_timeout(self.timeout) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => {
+                let message = format!("no response in {:?}", self.timeout);
+                self.kill(&message);
+                return None;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                self.alive = false;
+                return None;
+            }
+        };
+        match serde_json::from_str(&line) {
+            Ok(response) => Some(response),
+            Err(error) => {
+                self.kill(&format!("malformed response: {}", error));
+                None
+            }
+        }
+    }
+
+    fn kill(&mut self, message: &str) {
+        let notice = Message::Notice(Notice {
+            kind: String::from(KILL_NOTICE),
+            message: String::from(message),
+        });
+        let line = serde_json::to_string(&notice).expect("failed to serialize notice");
+        self.send(&line);
+        self.alive = false;
+    }
+
+    fn finish(mut self) {
+        self.stdin.take();
+        let deadline = Instant::now() + Duration::from_millis(FINISH_TIMEOUT_MS);
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(Some(_)) => return,
+                Ok(None) => thread::sleep(Duration::from_millis(FINISH_POLL_MS)),
+                Err(error) => panic!("failed to wait for strategy: {}", error),
+            }
+        }
+        eprintln!("strategy did not exit in {} ms, killing it", FINISH_TIMEOUT_MS);
+        let _ = self.child.kill();
+        self.child.wait().expect("failed to wait for strategy");
+    }
+}
+
+pub fn env_or<T: ::std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(
+        default,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kills_client_that_does_not_respond_in_time() {
+        let mut client = Client::spawn("cat", Duration::from_millis(100));
+        client.send(r#"{"X":1.0,"Y":2.0}"#);
+        let response = client.receive().expect("no echoed response");
+        assert_eq!((response.x, response.y), (1.0, 2.0));
+        let start = Instant::now();
+        assert!(client.receive().is_none());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(!client.alive);
+        let notice = client.lines.recv_timeout(Duration::from_secs(5)).expect("no kill notice");
+        match Message::parse(&notice) {
+            Ok(Message::Notice(notice)) => assert_eq!(notice.kind, KILL_NOTICE),
+            other => panic!("expected a kill notice, got {:?}", other),
+        }
+        client.finish();
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::env;
+use std::process;
+
+use interactor::read_world;
+use replay::Replay;
+use strategy::*;
+use strategy::mechanic::{observed_impulse, Mechanic, State};
+
+const POSITION_EPSILON: f64 = 1e-3;
+const VELOCITY_EPSILON: f64 = 1e-3;
+const MASS_EPSILON: f64 = 1e-6;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FieldErrors {
+    pub position: f64,
+    pub velocity: f64,
+    pub mass: f64,
+    pub radius: f64,
+    pub ttf: i64,
+    pub fragment_ids: bool,
+}
+
+impl FieldErrors {
+    pub fn is_divergent(&self) -> bool {
+        self.position > POSITION_EPSILON || self.velocity > VELOCITY_EPSILON ||
+            self.mass > MASS_EPSILON || self.radius > MASS_EPSILON || self.ttf != 0 ||
+            self.fragment_ids
+    }
+
+    fn merge(&mut self, other: &FieldErrors) {
+        self.position = self.position.max(other.position);
+        self.velocity = self.velocity.max(other.velocity);
+        self.mass = self.mass.max(other.mass);
+        self.radius = self.radius.max(other.radius);
+        self.ttf = self.ttf.max(other.ttf);
+        self.fragment_ids = self.fragment_ids || other.fragment_ids;
+    }
+}
+
+pub fn run() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() != 1 {
+        eprintln!("usage: simulation_check REPLAY");
+        process::exit(2);
+    }
+    let replay = Replay::load(&args[0]).expect("failed to load replay");
+
+    let worlds: Vec<_> = replay
+        .ticks
+        .iter()
+        .map(|replay_tick| {
+            read_world(replay_tick.tick, &replay_tick.data, &replay.config)
+        })
+        .collect();
+
+    println!("tick\tposition\tvelocity\tmass\tradius\tttf\tfragment_ids");
+    let mut total = FieldErrors::default();
+    let mut divergent_ticks = 0;
+    let mut first_divergence = None;
+    for i in 1..worlds.len().saturating_sub(1) {
+        if worlds[i].my_blobs.is_empty() || worlds[i + 1].my_blobs.is_empty() {
+            continue;
+        }
+        let replay_tick = &replay.ticks[i];
+        let state = observed_state(&worlds[i - 1], &worlds[i]);
+        let mut mechanic = Mechanic::new(&state, &replay.config);
+        let command = replay_tick.response.to_command(&replay.config);
+        mechanic.tick(&command, &worlds[i].food);
+        let errors = compare(&mechanic.state.my_blobs, &worlds[i + 1].my_blobs);
+        if errors.is_divergent() {
+            divergent_ticks += 1;
+            first_divergence.get_or_insert(replay_tick.tick);
+            println!(
+                "{}\t{:.4}\t{:.4}\t{:.4}\t{:.4}\t{}\t{}",
+                replay_tick.tick,
+                errors.position,
+                errors.velocity,
+                errors.mass,
+                errors.radius,
+                errors.ttf,
+                errors.fragment_ids
+            );
+        }
+        total.merge(&errors);
+    }
+    println!(
+        "max\t{:.4}\t{:.4}\t{:.4}\t{:.4}\t{}\t{}",
+        total.position,
+        total.velocity,
+        total.mass,
+        total.radius,
+        total.ttf,
+        total.fragment_ids
+    );
+    match first_divergence {
+        Some(tick) => {
+            println!(
+                "{} of {} ticks diverge, first at tick {}",
+                divergent_ticks,
+                replay.ticks.len(),
+                tick
+            )
+        }
+        None => println!("no divergence in {} ticks", replay.ticks.len()),
+    }
+}
+
+pub fn observed_state(previous: &World, current: &World) -> State {
+    let last_points: HashMap<_, _> = previous
+        .enemies
+        .iter()
+        .map(|enemy| (enemy.id(), enemy.point()))
+        .collect();
+    let enemies = current
+        .enemies
+        .iter()
+        .map(|enemy| {
+            let mut enemy = enemy.clone();
+            if let Some(&point) = last_points.get(&enemy.id()) {
+                let v = enemy.point() - point;
+                enemy.set_v(v);
+                enemy.update_is_fast(current.config);
+            }
+            enemy
+        })
+        .collect();
+    let ejections = current
+        .ejections
+        .iter()
+        .map(|ejection| {
+            let mut ejection = ejection.clone();
+            if let Some(last) = previous.ejections.iter().find(|last| last.id() == ejection.id()) {
+                ejection.set_v(observed_impulse(ejection.point() - last.point(), current.config));
+            }
+            ejection
+        })
+        .collect();
+    let viruses = current
+        .viruses
+        .iter()
+        .map(|virus| {
+            let mut virus = virus.clone();
+            if let Some(last) = previous.viruses.iter().find(|last| last.id() == virus.id()) {
+                virus.set_v(observed_impulse(virus.point() - last.point(), current.config));
+            }
+            virus
+        })
+        .collect();
+    State {
+        tick: current.tick,
+        my_blobs: current.my_blobs.clone(),
+        enemies,
+        ejections,
+        viruses,
+        ..Default::default()
+    }
+}
+
+pub fn compare(predicted: &[Player], observed: &[Player]) -> FieldErrors {
+    let mut errors = FieldErrors {
+        fragment_ids: predicted.len() != observed.len(),
+        ..Default::default()
+    };
+    for actual in observed.iter() {
+        match predicted.iter().find(|me| me.id() == actual.id()) {
+            Some(me) => {
+                errors.merge(&FieldErrors {
+                    position: me.point().dist(actual.point()),
+                    velocity: me.v().dist(actual.v()),
+                    mass: (me.m() - actual.m()).abs(),
+                    radius: (me.r() - actual.r()).abs(),
+                    ttf: (me.ttf() - actual.ttf()).abs(),
+                    fragment_ids: false,
+                })
+            }
+            None => errors.fragment_ids = true,
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::Config;
+
+    fn world<'a>(tick: Tick, ejection: Point, virus: Point, config: &'a Config) -> World<'a> {
+        World {
+            tick,
+            my_blobs: vec![],
+            food: vec![],
+            ejections: vec![
+                Ejection {
+                    id_: 1,
+                    point_: ejection,
+                    r_: config.ejection_radius,
+                    m_: config.ejection_mass,
+                    v_: Point::zero(),
+                },
+            ],
+            viruses: vec![
+                Virus {
+                    id_: 2,
+                    point_: virus,
+                    r_: config.virus_radius,
+                    m_: config.virus_mass,
+                    v_: Point::zero(),
+                    split_angle_: 0.0,
+                },
+            ],
+            enemies: vec![],
+            config,
+        }
+    }
+
+    #[test]
+    fn derives_moveable_velocity_from_previous_tick() {
+        let config = Config::default();
+        let previous = world(0, Point::new(100.0, 100.0), Point::new(200.0, 200.0), &config);
+        let current = world(1, Point::new(106.0, 108.0), Point::new(200.0, 200.0), &config);
+        let state = observed_state(&previous, &current);
+        let displacement = Point::new(6.0, 8.0);
+        let v = state.ejections[0].v();
+        assert!(v.dist(displacement.with_length(10.0 - config.viscosity)) < 1e-9);
+        assert_eq!(state.viruses[0].v().length(), 0.0);
+
+        let mut mechanic = Mechanic::new(&state, &config);
+        mechanic.tick(&Command::new(), &[]);
+        let moved = mechanic.state.ejections[0].point() - current.ejections[0].point();
+        assert!(moved.dist(v) < 1e-9);
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+use config::Config;
+use strategy::*;
+use strategy::grid::Grid;
+
+pub trait EnemyModel: Debug + Sync {
+    fn name(&self) -> &'static str;
+    fn command(&self, enemy: &Player, context: &EnemyContext) -> Option<Command>;
+}
+
+#[derive(Debug)]
+pub struct EnemyContext<'a> {
+    pub my_player_id: PlayerId,
+    pub players: &'a [Player],
+    pub food: &'a [Food],
+    pub food_grid: &'a Grid,
+    pub eaten_food: &'a HashSet<FoodId>,
+    pub config: &'a Config,
+}
+
+#[derive(Debug)]
+pub struct ConstantVelocity;
+
+#[derive(Debug)]
+pub struct GreedyChase;
+
+#[derive(Debug)]
+pub struct FoodSeeker;
+
+#[derive(Debug)]
+pub struct FleeFromBigger;
+
+#[derive(Debug)]
+pub struct SplitAttack;
+
+pub static CONSTANT_VELOCITY: ConstantVelocity = ConstantVelocity;
+pub static GREEDY_CHASE: GreedyChase = GreedyChase;
+pub static FOOD_SEEKER: FoodSeeker = FoodSeeker;
+pub static FLEE_FROM_BIGGER: FleeFromBigger = FleeFromBigger;
+pub static SPLIT_ATTACK: SplitAttack = SplitAttack;
+
+pub static ENEMY_MODELS: [&dyn EnemyModel; 5] = [
+    &CONSTANT_VELOCITY,
+    &GREEDY_CHASE,
+    &FOOD_SEEKER,
+    &FLEE_FROM_BIGGER,
+    &SPLIT_ATTACK,
+];
+
+const FLEE_DISTANCE: f64 = 100.0;
+
+impl EnemyModel for ConstantVelocity {
+    fn name(&self) -> &'static str {
+        "constant-velocity"
+    }
+
+    fn command(&self, _enemy: &Player, _context: &EnemyContext) -> Option<Command> {
+        None
+    }
+}
+
+impl EnemyModel for GreedyChase {
+    fn name(&self) -> &'static str {
+        "greedy-chase"
+    }
+
+    fn command(&self, enemy: &Player, context: &EnemyContext) -> Option<Command> {
+        nearest_prey(enemy, context).map(|me| {
+            Command::from_point(me.point(), context.config)
+        })
+    }
+}
+
+impl EnemyModel for FoodSeeker {
+    fn name(&self) -> &'static str {
+        "food-seeker"
+    }
+
+    fn command(&self, enemy: &Player, context: &EnemyContext) -> Option<Command> {
+        let vision_center = enemy.point() +
+            Point::from_polar(context.config.vis_shift, enemy.angle());
+        let mut found = vec![];
+        context.food_grid.query(
+            vision_center,
+            enemy.vision_radius(1, context.config),
+            &mut found,
+        );
+        found
+            .into_iter()
+            .map(|i| &context.food[i])
+            .filter(|food| {
+                !context.eaten_food.contains(&food.id()) &&
+                    enemy.can_see(*food, 1, context.config)
+            })
+            .min_by(|a, b| {
+                a.point()
+                    .qdist(enemy.point())
+                    .partial_cmp(&b.point().qdist(enemy.point()))
+                    .expect("incomparable distances")
+            })
+            .map(|food| Command::from_point(food.point(), context.config))
+    }
+}
+
+impl EnemyModel for FleeFromBigger {
+    fn name(&self) -> &'static str {
+        "flee-from-bigger"
+    }
+
+    fn command(&self, enemy: &Player, context: &EnemyContext) -> Option<Command> {
+        context
+            .players
+            .iter()
+            .filter(|other| {
+                other.player_id() != enemy.player_id() &&
+                    other.m() > enemy.m() * context.config.mass_eat_factor &&
+                    enemy.can_see(*other, 1, context.config)
+            })
+            .min_by(|a, b| {
+                a.point()
+                    .qdist(enemy.point())
+                    .partial_cmp(&b.point().qdist(enemy.point()))
+                    .expect("incomparable distances")
+            })
+            .map(|threat| {
+                let away = (enemy.point() - threat.point()).with_length(FLEE_DISTANCE);
+                Command::from_point(enemy.point() + away, context.config)
+            })
+    }
+}
+
+impl EnemyModel for SplitAttack {
+    fn name(&self) -> &'static str {
+        "split-attack"
+    }
+
+    fn command(&self, enemy: &Player, context: &EnemyContext) -> Option<Command> {
+        nearest_prey(enemy, context).map(|me| {
+            let mut command = Command::from_point(me.point(), context.config);
+            if enemy.m() > me.m() * 2.0 && enemy.can_split(1, context.config) {
+                command.set_split();
+            }
+            command
+        })
+    }
+}
+
+fn nearest_prey<'a>(enemy: &Player, context: &EnemyContext<'a>) -> Option<&'a Player> {
+    context
+        .players
+        .iter()
+        .filter(|me| {
+            me.player_id() == context.my_player_id && me.m() < enemy.m() &&
+                enemy.can_see(*me, 1, context.config)
+        })
+        .min_by(|a, b| {
+            a.point()
+                .qdist(enemy.point())
+                .partial_cmp(&b.point().qdist(enemy.point()))
+                .expect("incomparable distances")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::Config;
+
+    fn food(x: f64, y: f64, config: &Config) -> Food {
+        Food {
+            id_: FoodId {
+                x10: (x * 10.0).floor() as u32,
+                y10: (y * 10.0).floor() as u32,
+            },
+            point_: Point::new(x, y),
+            r_: config.food_radius,
+            m_: config.food_mass,
+        }
+    }
+
+    fn model_command(
+        model: &dyn EnemyModel,
+        players: &[Player],
+        food: &[Food],
+        config: &Config,
+    ) -> Option<Command> {
+        let eaten_food = food.iter().skip(1).map(|food| food.id()).collect();
+        let food_grid = Grid::with_blobs(config, food);
+        let context = EnemyContext {
+            my_player_id: 1,
+            players,
+            food,
+            food_grid: &food_grid,
+            eaten_food: &eaten_food,
+            config,
+        };
+        model.command(&players[0], &context)
+    }
+
+    #[test]
+    fn constant_velocity_sends_no_command() {
+        let config = Config::default();
+        let players = vec![
+            Player::new(2, 0, Point::new(500.0, 500.0), 100.0, &config),
+            Player::new(1, 0, Point::new(540.0, 500.0), 40.0, &config),
+        ];
+        assert!(model_command(&CONSTANT_VELOCITY, &players, &[], &config).is_none());
+    }
+
+    #[test]
+    fn greedy_chase_heads_for_smaller_me() {
+        let config = Config::default();
+        let players = vec![
+            Player::new(2, 0, Point::new(500.0, 500.0), 100.0, &config),
+            Player::new(1, 0, Point::new(540.0, 500.0), 40.0, &config),
+        ];
+        let command = model_command(&GREEDY_CHASE, &players, &[], &config).expect("no chase");
+        assert!(command.point().dist(players[1].point()) < 1e-9);
+        assert!(!command.split());
+
+        let players = vec![
+            Player::new(2, 0, Point::new(500.0, 500.0), 100.0, &config),
+            Player::new(1, 0, Point::new(540.0, 500.0), 200.0, &config),
+        ];
+        assert!(model_command(&GREEDY_CHASE, &players, &[], &config).is_none());
+    }
+
+    #[test]
+    fn food_seeker_heads_for_nearest_uneaten_food() {
+        let config = Config::default();
+        let players = vec![Player::new(2, 0, Point::new(500.0, 500.0), 100.0, &config)];
+        let food = vec![food(560.0, 500.0, &config), food(520.0, 500.0, &config), food(440.0, 500.0, &config)];
+        let command = model_command(&FOOD_SEEKER, &players, &food, &config).expect("no food");
+        assert!(command.point().dist(Point::new(560.0, 500.0)) < 1e-9);
+    }
+
+    #[test]
+    fn flee_from_bigger_runs_away_from_threat() {
+        let config = Config::default();
+        let players = vec![
+            Player::new(2, 0, Point::new(500.0, 500.0), 100.0, &config),
+            Player::new(3, 0, Point::new(540.0, 500.0), 200.0, &config),
+        ];
+        let command = model_command(&FLEE_FROM_BIGGER, &players, &[], &config).expect("no flight");
+        assert!(command.point().dist(Point::new(500.0 - FLEE_DISTANCE, 500.0)) < 1e-9);
+
+        let players = vec![
+            Player::new(2, 0, Point::new(500.0, 500.0), 100.0, &config),
+            Player::new(3, 0, Point::new(540.0, 500.0), 110.0, &config),
+        ];
+        assert!(model_command(&FLEE_FROM_BIGGER, &players, &[], &config).is_none());
+    }
+
+    #[test]
+    fn split_attack_splits_on_less_than_half_its_mass() {
+        let config = Config::default();
+        let players = vec![
+            Player::new(2, 0, Point::new(500.0, 500.0), 300.0, &config),
+            Player::new(1, 0, Point::new(560.0, 500.0), 149.0, &config),
+        ];
+        let command = model_command(&SPLIT_ATTACK, &players, &[], &config).expect("no attack");
+        assert!(command.point().dist(players[1].point()) < 1e-9);
+        assert!(command.split());
+
+        let players = vec![
+            Player::new(2, 0, Point::new(500.0, 500.0), 300.0, &config),
+            Player::new(1, 0, Point::new(560.0, 500.0), 151.0, &config),
+        ];
+        let command = model_command(&SPLIT_ATTACK, &players, &[], &config).expect("no attack");
+        assert!(command.point().dist(players[1].point()) < 1e-9);
+        assert!(!command.split());
+    }
+}
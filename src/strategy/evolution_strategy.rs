@@ -0,0 +1,312 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use config::Config;
+use strategy::*;
+use strategy::mechanic::State;
+use strategy::planner::PlannerCore;
+use strategy::scoring::Score;
+use strategy::simulator::*;
+use version::VERSION;
+
+const POPULATION_SIZE: usize = 12;
+const ELITE_COUNT: usize = 2;
+const TOURNAMENT_SIZE: usize = 3;
+const COMMAND_DISTANCE_FACTOR: f64 = 2.0;
+const MUTATION_PROBABILITY: f64 = 0.3;
+const MUTATION_DISTANCE_FACTOR: f64 = 0.5;
+const SPLIT_PROBABILITY: f64 = 0.05;
+const SPLIT_FLIP_PROBABILITY: f64 = 0.1;
+
+#[derive(Debug)]
+pub struct EvolutionStrategy {
+    core: PlannerCore,
+    commands: VecDeque<Command>,
+    rng: XorShiftRng,
+    best: Option<Individual>,
+
+    evaluations: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Individual {
+    genes: Vec<Gene>,
+    fitness: Score,
+    blob_count: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Gene {
+    target: Point,
+    split: bool,
+}
+
+impl EvolutionStrategy {
+    pub fn new() -> EvolutionStrategy {
+        EvolutionStrategy {
+            core: PlannerCore::new(),
+            commands: Default::default(),
+            rng: XorShiftRng::from_seed([0x1337_5EED; 4]),
+            best: None,
+
+            evaluations: Default::default(),
+        }
+    }
+
+    fn tick_impl(&mut self, world: &World) -> Command {
+        self.core.start_tick(world);
+        let on_plan = self.best.as_ref().map_or(false, |best| {
+            best.blob_count == self.core.tracker.state.my_blobs.len()
+        });
+        if self.commands.is_empty() || !on_plan {
+            self.commands.clear();
+            self.core.update_skips();
+            self.plan();
+        }
+        let mut command = self.commands.pop_front().expect("no commands left");
+        if self.core.tracker.state.tick == 0 {
+            command.add_debug_message(format!("running evolution strategy version {}", VERSION));
+        }
+        #[cfg(feature = "debug")]
+        {
+            command.add_debug_message(format!("skips:\t{}", self.core.skips));
+            command.add_debug_message(format!("evaluations:\t{}", self.evaluations));
+            if let Some(ref best) = self.best {
+                command.add_debug_message(format!("fitness:\t{:.2}", best.fitness));
+            }
+        }
+        command
+    }
+
+    fn plan(&mut self) {
+        let simulator = self.core.simulator();
+        let root = self.core.tracker.state.clone();
+        let depth = simulator.depth.max(1) as usize;
+        self.evaluations = 0;
+
+        let mut population = vec![];
+        if let Some(best) = self.best.take() {
+            let mut genes = best.genes;
+            if !genes.is_empty() {
+                genes.remove(0);
+            }
+            genes.truncate(depth);
+            while genes.len() < depth {
+                let gene = self.random_gene(&root, &simulator.config);
+                genes.push(gene);
+            }
+            population.push(genes);
+        }
+        while population.len() < POPULATION_SIZE {
+            let genes = (0..depth)
+                .map(|_| self.random_gene(&root, &simulator.config))
+                .collect();
+            population.push(genes);
+        }
+        let mut population: Vec<_> = population
+            .into_iter()
+            .map(|genes| self.evaluate(&simulator, &root, genes))
+            .collect();
+
+        while self.core.within_budget(self.evaluations) {
+            population.sort_by(|a, b| {
+                b.fitness.partial_cmp(&a.fitness).expect("incomparable fitness")
+            });
+            let mut next: Vec<_> = population.iter().take(ELITE_COUNT).cloned().collect();
+            while next.len() < POPULATION_SIZE && self.core.within_budget(self.evaluations) {
+                let genes = {
+                    let a = self.select(&population);
+                    let b = self.select(&population);
+                    self.crossover(&a.genes, &b.genes)
+                };
+                let genes = self.mutate(genes, &root, &simulator.config);
+                next.push(self.evaluate(&simulator, &root, genes));
+            }
+            if next.len() < POPULATION_SIZE {
+                next.extend(population.into_iter().skip(ELITE_COUNT));
+                next.truncate(POPULATION_SIZE);
+            }
+            population = next;
+        }
+
+        let best = population
+            .into_iter()
+            .max_by(|a, b| {
+                a.fitness.partial_cmp(&b.fitness).expect("incomparable fitness")
+            })
+            .expect("empty population");
+        let config = &simulator.config;
+        self.commands.extend(gene_commands(best.genes[0], config, self.core.skips));
+        self.best = Some(best);
+    }
+
+    fn evaluate(&mut self, simulator: &Simulator, root: &State, genes: Vec<Gene>) -> Individual {
+        self.evaluations += 1;
+        let mut state = root.clone();
+        let mut blob_count = root.my_blobs.len();
+        for (i, &gene) in genes.iter().enumerate() {
+            let commands = gene_commands(gene, &simulator.config, self.core.skips);
+            state = simulator.predict_states(&state, &commands);
+            if i == 0 {
+                blob_count = state.my_blobs.len();
+            }
+        }
+        Individual {
+            genes,
+            fitness: self.core.scorer.score(&state, &self.core.tracker),
+            blob_count,
+        }
+    }
+
+    fn select<'a>(&mut self, population: &'a [Individual]) -> &'a Individual {
+        (0..TOURNAMENT_SIZE)
+            .map(|_| &population[self.rng.gen_range(0, population.len())])
+            .max_by(|a, b| {
+                a.fitness.partial_cmp(&b.fitness).expect("incomparable fitness")
+            })
+            .expect("empty tournament")
+    }
+
+    fn crossover(&mut self, a: &[Gene], b: &[Gene]) -> Vec<Gene> {
+        let point = self.rng.gen_range(0, a.len().min(b.len()) + 1);
+        a[..point].iter().chain(b[point..].iter()).cloned().collect()
+    }
+
+    fn mutate(&mut self, mut genes: Vec<Gene>, root: &State, config: &Config) -> Vec<Gene> {
+        let distance = command_distance(root, config) * MUTATION_DISTANCE_FACTOR;
+        for gene in genes.iter_mut() {
+            if self.rng.next_f64() < MUTATION_PROBABILITY {
+                let offset = Point::from_polar(
+                    self.rng.next_f64() * distance,
+                    self.rng.next_f64() * 2.0 * PI,
+                );
+                gene.target = clamp_to_map(gene.target + offset, config);
+            }
+            if self.rng.next_f64() < SPLIT_FLIP_PROBABILITY {
+                gene.split = !gene.split;
+            }
+        }
+        genes
+    }
+
+    fn random_gene(&mut self, root: &State, config: &Config) -> Gene {
+        let me = biggest_blob(root).expect("random_gene with no blobs");
+        let offset = Point::from_polar(
+            command_distance(root, config),
+            self.rng.next_f64() * 2.0 * PI,
+        );
+        Gene {
+            target: clamp_to_map(me.point() + offset, config),
+            split: self.rng.next_f64() < SPLIT_PROBABILITY,
+        }
+    }
+}
+
+impl Default for EvolutionStrategy {
+    fn default() -> EvolutionStrategy {
+        EvolutionStrategy::new()
+    }
+}
+
+impl Planner for EvolutionStrategy {
+    fn core_mut(&mut self) -> &mut PlannerCore {
+        &mut self.core
+    }
+}
+
+impl Strategy for EvolutionStrategy {
+    fn on_start(&mut self, config: &Config) {
+        self.core.tracker.set_config(config);
+    }
+
+    fn tick(&mut self, world: &World) -> Command {
+        if world.my_blobs.is_empty() {
+            return Default::default();
+        }
+        self.tick_impl(world)
+    }
+}
+
+fn gene_commands(gene: Gene, config: &Config, skips: i64) -> Vec<Command> {
+    (0..skips)
+        .map(|i| {
+            let mut command = Command::from_point(gene.target, config);
+            if gene.split && i == 0 {
+                command.set_split();
+            }
+            command
+        })
+        .collect()
+}
+
+fn command_distance(state: &State, config: &Config) -> f64 {
+    let me = biggest_blob(state).expect("command_distance with no blobs");
+    me.vision_radius(state.my_blobs.len(), config) * COMMAND_DISTANCE_FACTOR
+}
+
+fn clamp_to_map(point: Point, config: &Config) -> Point {
+    Point::new(
+        point.x.max(0.0).min(config.game_width as f64),
+        point.y.max(0.0).min(config.game_height as f64),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world<'a>(tick: Tick, config: &'a Config) -> World<'a> {
+        World {
+            tick,
+            my_blobs: vec![Player::new(1, 0, Point::new(300.0, 300.0), 100.0, config)],
+            food: vec![],
+            ejections: vec![],
+            viruses: vec![],
+            enemies: vec![],
+            config,
+        }
+    }
+
+    fn gene(x: f64) -> Gene {
+        Gene {
+            target: Point::new(x, 0.0),
+            split: false,
+        }
+    }
+
+    #[test]
+    fn crossover_handles_different_gene_lengths() {
+        let mut strategy = EvolutionStrategy::new();
+        let short = vec![gene(1.0); 2];
+        let long = vec![gene(2.0); 5];
+        for _ in 0..100 {
+            let child = strategy.crossover(&short, &long);
+            assert_eq!(child.len(), long.len());
+            let child = strategy.crossover(&long, &short);
+            assert_eq!(child.len(), short.len());
+        }
+    }
+
+    #[test]
+    fn plans_across_depth_changes() {
+        let config = Config::default();
+        let mut strategy = EvolutionStrategy::new();
+        strategy.on_start(&config);
+        strategy.set_budget(Budget::Paths(POPULATION_SIZE * 2));
+        let mut tick = 0;
+        for &depth in [7, 3, 0, 9].iter() {
+            strategy.set_search_params(SearchParams {
+                simulation_depth: depth,
+                ..Default::default()
+            });
+            for _ in 0..20 {
+                strategy.tick(&world(tick, &config));
+                tick += 1;
+            }
+            let best = strategy.best.as_ref().expect("no plan");
+            assert_eq!(best.genes.len(), depth.max(1) as usize);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use config::Config;
+use strategy::*;
+
+pub const FOOD_MAP_CELLS: usize = 16;
+const FOOD_DECAY: f64 = 0.998;
+const FOOD_RESPAWN: f64 = 0.004;
+
+#[derive(Debug, Clone, Default)]
+pub struct FoodMap {
+    cell_width: f64,
+    cell_height: f64,
+    density: Vec<f64>,
+    last_seen: Vec<Option<Tick>>,
+}
+
+impl FoodMap {
+    pub fn new(config: &Config) -> FoodMap {
+        let cells = FOOD_MAP_CELLS * FOOD_MAP_CELLS;
+        FoodMap {
+            cell_width: config.game_width as f64 / FOOD_MAP_CELLS as f64,
+            cell_height: config.game_height as f64 / FOOD_MAP_CELLS as f64,
+            density: vec![FOOD_RESPAWN / (1.0 - FOOD_DECAY); cells],
+            last_seen: vec![None; cells],
+        }
+    }
+
+    pub fn update(&mut self, tick: Tick, food: &[Food], my_blobs: &[Player], config: &Config) {
+        if self.density.is_empty() {
+            return;
+        }
+        let mut observed = vec![0.0; self.density.len()];
+        for food in food.iter() {
+            let cell = self.cell(food.point());
+            observed[cell] += 1.0;
+        }
+        let half_diagonal = self.cell_width.hypot(self.cell_height) / 2.0;
+        for (cell, &observed) in observed.iter().enumerate() {
+            let center = self.center(cell);
+            let visible = my_blobs.iter().any(|me| {
+                let vision_center = me.point() + Point::from_polar(config.vis_shift, me.angle());
+                let radius = me.vision_radius(my_blobs.len(), config) + half_diagonal;
+                center.qdist(vision_center) < radius.powi(2)
+            });
+            if visible {
+                self.density[cell] = observed;
+                self.last_seen[cell] = Some(tick);
+            } else {
+                self.density[cell] = self.density[cell] * FOOD_DECAY + FOOD_RESPAWN;
+            }
+        }
+    }
+
+    pub fn density(&self, cell: usize) -> f64 {
+        self.density[cell]
+    }
+
+    pub fn last_seen(&self, cell: usize) -> Option<Tick> {
+        self.last_seen[cell]
+    }
+
+    pub fn len(&self) -> usize {
+        self.density.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.density.is_empty()
+    }
+
+    pub fn center(&self, cell: usize) -> Point {
+        Point::new(
+            ((cell % FOOD_MAP_CELLS) as f64 + 0.5) * self.cell_width,
+            ((cell / FOOD_MAP_CELLS) as f64 + 0.5) * self.cell_height,
+        )
+    }
+
+    pub fn targets(&self, tick: Tick, from: Point, count: usize) -> Vec<Point> {
+        let cell_size = self.cell_width.max(self.cell_height);
+        let mut cells: Vec<_> = (0..self.density.len())
+            .filter(|&cell| self.last_seen[cell] != Some(tick))
+            .map(|cell| {
+                let value = self.density[cell] / (1.0 + from.dist(self.center(cell)) / cell_size);
+                (value, cell)
+            })
+            .collect();
+        cells.sort_by(|a, b| b.0.partial_cmp(&a.0).expect("incomparable food values"));
+        cells
+            .into_iter()
+            .take(count)
+            .map(|(_, cell)| self.center(cell))
+            .collect()
+    }
+
+    fn cell(&self, point: Point) -> usize {
+        let column = ((point.x / self.cell_width) as usize).min(FOOD_MAP_CELLS - 1);
+        let row = ((point.y / self.cell_height) as usize).min(FOOD_MAP_CELLS - 1);
+        row * FOOD_MAP_CELLS + column
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HIDDEN_TICKS: Tick = 100;
+
+    fn food_at(point: Point, count: u32, config: &Config) -> Vec<Food> {
+        (0..count)
+            .map(|i| Food {
+                id_: FoodId {
+                    x10: (point.x * 10.0) as u32 + i,
+                    y10: (point.y * 10.0) as u32,
+                },
+                point_: point,
+                r_: config.food_radius,
+                m_: config.food_mass,
+            })
+            .collect()
+    }
+
+    fn me_at(point: Point, config: &Config) -> Vec<Player> {
+        vec![Player::new(1, 0, point, 40.0, config)]
+    }
+
+    // Sees 50 food at a, then watches b for HIDDEN_TICKS with nothing to see.
+    fn observed_map(a: Point, b: Point, config: &Config) -> FoodMap {
+        let mut food_map = FoodMap::new(config);
+        food_map.update(0, &food_at(a, 50, config), &me_at(a, config), config);
+        for tick in 1..HIDDEN_TICKS {
+            food_map.update(tick, &[], &me_at(b, config), config);
+        }
+        food_map
+    }
+
+    #[test]
+    fn decays_hidden_cells_toward_the_respawn_level() {
+        let config = Config::default();
+        let (a, b) = (Point::new(100.0, 100.0), Point::new(900.0, 900.0));
+        let food_map = observed_map(a, b, &config);
+        let hidden = food_map.cell(a);
+        let decay = FOOD_DECAY.powi(HIDDEN_TICKS as i32 - 1);
+        let expected = 50.0 * decay + FOOD_RESPAWN * (1.0 - decay) / (1.0 - FOOD_DECAY);
+        assert!((food_map.density(hidden) - expected).abs() < 1e-9);
+        assert_eq!(food_map.last_seen(hidden), Some(0));
+        let watched = food_map.cell(b);
+        assert_eq!(food_map.density(watched), 0.0);
+        assert_eq!(food_map.last_seen(watched), Some(HIDDEN_TICKS - 1));
+    }
+
+    #[test]
+    fn targets_the_densest_cell_not_seen_this_tick() {
+        let config = Config::default();
+        let (a, b) = (Point::new(100.0, 100.0), Point::new(900.0, 900.0));
+        let mut food_map = observed_map(a, b, &config);
+        food_map.update(HIDDEN_TICKS, &food_at(b, 50, &config), &me_at(b, &config), &config);
+        assert_eq!(food_map.density(food_map.cell(b)), 50.0);
+        let target = food_map.targets(HIDDEN_TICKS, b, 1).pop().expect("no target");
+        assert!(target.dist(food_map.center(food_map.cell(a))) < 1e-9);
+    }
+}
@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+use std::mem;
+
+use config::Config;
+use strategy::*;
+
+const COINCIDENCE_DISTANCE: f64 = 0.01;
+const MASS_TOLERANCE: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Split,
+    Burst,
+    Fusion,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Lineage {
+    pub parent: PlayerBlobId,
+    pub event: Event,
+    pub tick: Tick,
+}
+
+#[derive(Debug, Default)]
+pub struct FragmentTracker {
+    lineages: BTreeMap<PlayerBlobId, Lineage>,
+}
+
+impl FragmentTracker {
+    pub fn new() -> FragmentTracker {
+        Default::default()
+    }
+
+    pub fn lineage(&self, id: PlayerBlobId) -> Option<&Lineage> {
+        self.lineages.get(&id)
+    }
+
+    pub fn linked_at(&self, id: PlayerBlobId, tick: Tick) -> bool {
+        self.lineage(id).map_or(false, |lineage| lineage.tick == tick)
+    }
+
+    // Links enemies to the blobs seen on the previous tick that split, burst or fused into them,
+    // and infers their velocity, speed and TTF. Returns the ids of the blobs that are gone.
+    pub fn update(
+        &mut self,
+        tick: Tick,
+        previous: &[Player],
+        enemies: &mut [Player],
+        viruses: &[Virus],
+        config: &Config,
+    ) -> Vec<PlayerBlobId> {
+        let lineages = mem::replace(&mut self.lineages, BTreeMap::new());
+        self.lineages = lineages
+            .into_iter()
+            .filter(|&(_, lineage)| tick - lineage.tick < config.ticks_til_fusion)
+            .collect();
+        let mut gone = vec![];
+        self.link_splits(tick, previous, enemies, viruses, &mut gone, config);
+        self.link_fusions(tick, previous, enemies, &mut gone, config);
+        gone
+    }
+
+    fn link_splits(
+        &mut self,
+        tick: Tick,
+        previous: &[Player],
+        enemies: &mut [Player],
+        viruses: &[Virus],
+        gone: &mut Vec<PlayerBlobId>,
+        config: &Config,
+    ) {
+        let mut new_fragments: Vec<_> = (0..enemies.len())
+            .filter(|&i| !contains(previous, enemies[i].id()))
+            .collect();
+        while !new_fragments.is_empty() {
+            let player_id = enemies[new_fragments[0]].player_id();
+            let point = enemies[new_fragments[0]].point();
+            let mut group: Vec<_> = new_fragments
+                .iter()
+                .cloned()
+                .filter(|&i| {
+                    enemies[i].player_id() == player_id &&
+                        enemies[i].point().dist(point) < COINCIDENCE_DISTANCE
+                })
+                .collect();
+            new_fragments.retain(|i| !group.contains(i));
+            if group.len() < 2 {
+                continue;
+            }
+            let m: f64 = group.iter().map(|&i| enemies[i].m()).sum();
+            let parent = match previous
+                .iter()
+                .filter(|parent| {
+                    parent.player_id() == player_id && !contains(enemies, parent.id()) &&
+                        !gone.contains(&parent.id()) &&
+                        parent.point().dist(point) <=
+                            parent.speed().max(parent.max_speed(config)) +
+                                COINCIDENCE_DISTANCE &&
+                        (similar_mass(m, parent.m(), config) ||
+                             similar_mass(m, parent.m() + config.burst_bonus, config))
+                })
+                .min_by(|a, b| {
+                    a.point()
+                        .qdist(point)
+                        .partial_cmp(&b.point().qdist(point))
+                        .expect("incomparable distances")
+                }) {
+                Some(parent) => parent,
+                None => continue,
+            };
+            let event = if group.len() > 2 ||
+                (m - parent.m() - config.burst_bonus).abs() < (m - parent.m()).abs()
+            {
+                Event::Burst
+            } else {
+                Event::Split
+            };
+            group.sort_unstable_by_key(|&i| enemies[i].fragment_id());
+            let displacement = point - parent.point();
+            let children = group.len() - 1;
+            match event {
+                Event::Split => {
+                    let child = &mut enemies[group[0]];
+                    child.set_v(Point::from_polar(
+                        config.split_start_speed,
+                        displacement.angle(),
+                    ));
+                    child.set_fast(true);
+                    let rest = &mut enemies[group[1]];
+                    rest.set_v(displacement);
+                    rest.update_is_fast(config);
+                }
+                _ => {
+                    let angle = viruses
+                        .iter()
+                        .min_by(|a, b| {
+                            a.point()
+                                .qdist(point)
+                                .partial_cmp(&b.point().qdist(point))
+                                .expect("incomparable distances")
+                        })
+                        .map_or((parent.point() - point).angle(), |virus| {
+                            (point - virus.point()).angle()
+                        });
+                    let spectrum = config.burst_angle_spectrum;
+                    for (k, &i) in group.iter().enumerate() {
+                        let fragment_angle = if k == children {
+                            angle + spectrum / 2.0
+                        } else {
+                            angle - spectrum / 2.0 + k as f64 * spectrum / children as f64
+                        };
+                        enemies[i].set_v(
+                            Point::from_polar(config.burst_start_speed, fragment_angle),
+                        );
+                        enemies[i].set_fast(true);
+                    }
+                }
+            }
+            for &i in group.iter() {
+                enemies[i].set_ttf(config.ticks_til_fusion);
+                self.lineages.insert(
+                    enemies[i].id(),
+                    Lineage {
+                        parent: parent.id(),
+                        event,
+                        tick,
+                    },
+                );
+            }
+            gone.push(parent.id());
+        }
+    }
+
+    fn link_fusions(
+        &mut self,
+        tick: Tick,
+        previous: &[Player],
+        enemies: &mut [Player],
+        gone: &mut Vec<PlayerBlobId>,
+        config: &Config,
+    ) {
+        for i in 0..enemies.len() {
+            if self.linked_at(enemies[i].id(), tick) {
+                continue;
+            }
+            let survivor = previous.iter().find(|blob| blob.id() == enemies[i].id());
+            let absorbed: Vec<_> = {
+                let enemy = &enemies[i];
+                previous
+                    .iter()
+                    .filter(|blob| {
+                        blob.player_id() == enemy.player_id() && !contains(enemies, blob.id()) &&
+                            !gone.contains(&blob.id()) &&
+                            blob.point().dist(enemy.point()) <=
+                                blob.r() + enemy.r() + blob.max_speed(config)
+                    })
+                    .collect()
+            };
+            if absorbed.is_empty() || (survivor.is_none() && absorbed.len() < 2) {
+                continue;
+            }
+            let survivor_m = survivor.map_or(0.0, |blob| blob.m());
+            let absorbed_m: f64 = absorbed.iter().map(|blob| blob.m()).sum();
+            if !similar_mass(enemies[i].m(), survivor_m + absorbed_m, config) {
+                continue;
+            }
+            let fused: Vec<_> = survivor.into_iter().chain(absorbed.iter().cloned()).collect();
+            let sum_m = survivor_m + absorbed_m;
+            let v = fused.iter().fold(Point::zero(), |v, blob| {
+                v + blob.v() * (blob.m() / sum_m)
+            });
+            let parent = fused
+                .iter()
+                .max_by(|a, b| a.m().partial_cmp(&b.m()).expect("incomparable mass"))
+                .expect("no fused blobs");
+            let enemy = &mut enemies[i];
+            enemy.set_fast(parent.is_fast());
+            if enemy.is_fast() {
+                enemy.set_v(v);
+            } else {
+                enemy.set_v(v.limit_length(enemy.max_speed(config)));
+            }
+            enemy.set_ttf(survivor.map_or(0, |blob| (blob.ttf() - 1).max(0)));
+            self.lineages.insert(
+                enemy.id(),
+                Lineage {
+                    parent: parent.id(),
+                    event: Event::Fusion,
+                    tick,
+                },
+            );
+            gone.extend(absorbed.iter().map(|blob| blob.id()));
+        }
+    }
+}
+
+fn contains(blobs: &[Player], id: PlayerBlobId) -> bool {
+    blobs.iter().any(|blob| blob.id() == id)
+}
+
+fn similar_mass(a: Mass, b: Mass, config: &Config) -> bool {
+    (a - b).abs() <= MASS_TOLERANCE * a.max(b) + config.food_mass
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob(fragment_id: FragmentId, point: Point, m: Mass, config: &Config) -> Player {
+        Player::new(2, fragment_id, point, m, config)
+    }
+
+    fn parent_id() -> PlayerBlobId {
+        PlayerBlobId {
+            player_id: 2,
+            fragment_id: 0,
+        }
+    }
+
+    #[test]
+    fn links_split_fragments_to_their_parent() {
+        let config = Config::default();
+        let previous = vec![blob(0, Point::new(500.0, 500.0), 200.0, &config)];
+        let point = Point::new(500.5, 500.0);
+        let mut enemies = vec![blob(1, point, 100.0, &config), blob(2, point, 100.0, &config)];
+        let mut tracker = FragmentTracker::new();
+        let gone = tracker.update(1, &previous, &mut enemies, &[], &config);
+        assert_eq!(gone, vec![parent_id()]);
+        for enemy in enemies.iter() {
+            let lineage = tracker.lineage(enemy.id()).expect("fragment not linked");
+            assert_eq!(
+                (lineage.parent, lineage.event, lineage.tick),
+                (parent_id(), Event::Split, 1)
+            );
+            assert_eq!(enemy.ttf(), config.ticks_til_fusion);
+        }
+        assert!(enemies[0].is_fast());
+        assert!(enemies[0].v().dist(Point::new(config.split_start_speed, 0.0)) < 1e-9);
+    }
+
+    #[test]
+    fn links_burst_fragments_to_their_parent() {
+        let config = Config::default();
+        let previous = vec![blob(0, Point::new(500.0, 500.0), 200.0, &config)];
+        let point = Point::new(500.5, 500.0);
+        let m = (200.0 + config.burst_bonus) / 3.0;
+        let mut enemies: Vec<_> = (1..4)
+            .map(|fragment_id| blob(fragment_id, point, m, &config))
+            .collect();
+        let viruses = vec![
+            Virus {
+                id_: 1,
+                point_: Point::new(540.0, 500.0),
+                r_: config.virus_radius,
+                m_: config.virus_mass,
+                v_: Point::zero(),
+                split_angle_: 0.0,
+            },
+        ];
+        let mut tracker = FragmentTracker::new();
+        let gone = tracker.update(1, &previous, &mut enemies, &viruses, &config);
+        assert_eq!(gone, vec![parent_id()]);
+        for enemy in enemies.iter() {
+            let lineage = tracker.lineage(enemy.id()).expect("fragment not linked");
+            assert_eq!((lineage.parent, lineage.event), (parent_id(), Event::Burst));
+            assert!(enemy.is_fast());
+            assert!((enemy.speed() - config.burst_start_speed).abs() < 1e-9);
+        }
+        let v = enemies.iter().fold(Point::zero(), |v, enemy| v + enemy.v());
+        assert!(v.x < 0.0);
+    }
+
+    #[test]
+    fn links_fused_blob_to_the_heaviest_part() {
+        let config = Config::default();
+        let previous = vec![
+            blob(0, Point::new(500.0, 500.0), 100.0, &config),
+            blob(1, Point::new(510.0, 500.0), 50.0, &config),
+        ];
+        let mut enemies = vec![blob(0, Point::new(502.0, 500.0), 150.0, &config)];
+        let mut tracker = FragmentTracker::new();
+        let gone = tracker.update(1, &previous, &mut enemies, &[], &config);
+        assert_eq!(
+            gone,
+            vec![
+                PlayerBlobId {
+                    player_id: 2,
+                    fragment_id: 1,
+                },
+            ]
+        );
+        let lineage = tracker.lineage(parent_id()).expect("fusion not linked");
+        assert_eq!((lineage.parent, lineage.event), (parent_id(), Event::Fusion));
+        assert!(tracker.linked_at(parent_id(), 1));
+        assert!(!tracker.linked_at(parent_id(), 2));
+    }
+}
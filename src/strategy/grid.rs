@@ -0,0 +1,157 @@
+use config::Config;
+use models::*;
+
+pub const GRID_CELL_SIZE: f64 = 32.0;
+const MIN_INDEXED_BLOBS: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct Grid {
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    max_r: f64,
+    entries: Vec<(usize, usize)>,
+}
+
+impl Grid {
+    pub fn new(width: f64, height: f64, cell_size: f64) -> Grid {
+        Grid {
+            cell_size,
+            columns: ((width / cell_size).ceil() as usize).max(1),
+            rows: ((height / cell_size).ceil() as usize).max(1),
+            max_r: 0.0,
+            entries: Default::default(),
+        }
+    }
+
+    pub fn for_config(config: &Config) -> Grid {
+        Grid::new(
+            config.game_width as f64,
+            config.game_height as f64,
+            GRID_CELL_SIZE,
+        )
+    }
+
+    // A single cell, so every query returns every blob like a linear scan.
+    pub fn linear(config: &Config) -> Grid {
+        let size = config.game_width.max(config.game_height) as f64;
+        Grid::new(size, size, size)
+    }
+
+    pub fn with_blobs<B: Circle>(config: &Config, blobs: &[B]) -> Grid {
+        let mut grid = Grid::for_config(config);
+        grid.rebuild(blobs);
+        grid
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn rebuild<B: Circle>(&mut self, blobs: &[B]) {
+        self.max_r = 0.0;
+        self.entries.clear();
+        for (i, blob) in blobs.iter().enumerate() {
+            self.max_r = self.max_r.max(blob.r());
+            let cell = self.cell(blob.point());
+            self.entries.push((cell, i));
+        }
+        if self.entries.len() >= MIN_INDEXED_BLOBS {
+            self.entries.sort_unstable();
+        }
+    }
+
+    pub fn query(&self, center: Point, radius: f64, found: &mut Vec<usize>) {
+        found.clear();
+        if self.entries.len() < MIN_INDEXED_BLOBS {
+            found.extend(0..self.entries.len());
+            return;
+        }
+        let reach = radius + self.max_r;
+        let (first_column, first_row) = self.coords(center.x - reach, center.y - reach);
+        let (last_column, last_row) = self.coords(center.x + reach, center.y + reach);
+        for row in first_row..last_row + 1 {
+            let first = row * self.columns + first_column;
+            let last = row * self.columns + last_column;
+            let start = match self.entries.binary_search(&(first, 0)) {
+                Ok(start) | Err(start) => start,
+            };
+            found.extend(
+                self.entries[start..]
+                    .iter()
+                    .take_while(|&&(cell, _)| cell <= last)
+                    .map(|&(_, i)| i),
+            );
+        }
+        found.sort_unstable();
+    }
+
+    fn cell(&self, point: Point) -> usize {
+        let (column, row) = self.coords(point.x, point.y);
+        row * self.columns + column
+    }
+
+    fn coords(&self, x: f64, y: f64) -> (usize, usize) {
+        let column = (x / self.cell_size).max(0.0) as usize;
+        let row = (y / self.cell_size).max(0.0) as usize;
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn food(i: usize, config: &Config) -> Food {
+        let point = Point::new((i * 37 % 990) as f64, (i * 91 % 990) as f64);
+        Food {
+            id_: FoodId {
+                x10: (point.x * 10.0).floor() as u32,
+                y10: (point.y * 10.0).floor() as u32,
+            },
+            point_: point,
+            r_: config.food_radius + (i % 7) as f64,
+            m_: config.food_mass,
+        }
+    }
+
+    #[test]
+    fn query_finds_everything_a_linear_scan_finds() {
+        let config = Config::default();
+        let blobs: Vec<_> = (0..500).map(|i| food(i, &config)).collect();
+        let grid = Grid::with_blobs(&config, &blobs);
+        let mut found = vec![];
+        for &(x, y, radius) in [
+            (0.0, 0.0, 10.0),
+            (500.0, 500.0, 50.0),
+            (989.0, 10.0, 100.0),
+            (-20.0, 1200.0, 300.0),
+            (320.0, 640.0, 0.0),
+        ].iter()
+        {
+            let center = Point::new(x, y);
+            grid.query(center, radius, &mut found);
+            assert!(found.len() < blobs.len() || radius >= 300.0);
+            for (i, blob) in blobs.iter().enumerate() {
+                if blob.point().dist(center) <= radius + blob.r() {
+                    assert!(found.contains(&i), "missed {} around {:?}", i, center);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn linear_grid_returns_every_blob() {
+        let config = Config::default();
+        let blobs: Vec<_> = (0..100).map(|i| food(i, &config)).collect();
+        let mut grid = Grid::linear(&config);
+        grid.rebuild(&blobs);
+        let mut found = vec![];
+        grid.query(Point::new(0.0, 0.0), 1.0, &mut found);
+        assert_eq!(found, (0..blobs.len()).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::VecDeque;
+use std::f64;
+use std::f64::consts::PI;
+use std::mem;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use config::Config;
+use strategy::*;
+use strategy::enemy_model::{EnemyModel, ENEMY_MODELS};
+use strategy::mechanic::State;
+use strategy::planner::PlannerCore;
+use strategy::scoring::Score;
+use strategy::simulator::*;
+use strategy::tree::{NodeId, Tree, ROOT};
+use strategy::world_tracker::WorldTracker;
+use version::VERSION;
+
+const ACTION_DIRECTIONS: usize = 12;
+const COMMAND_DISTANCE_FACTOR: f64 = 2.0;
+const EXPLORATION_FACTOR: f64 = 1.4;
+
+#[derive(Debug)]
+pub struct MctsStrategy {
+    core: PlannerCore,
+    tree: Tree,
+    stats: Vec<Stats>,
+    commands: VecDeque<Command>,
+    rng: XorShiftRng,
+
+    next_root: Option<NodeId>,
+    planned_blob_count: usize,
+    min_value: Score,
+    max_value: Score,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    depth: i64,
+    visits: i64,
+    total_value: Score,
+    untried: Vec<Action>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Action {
+    angle: Angle,
+    split: bool,
+}
+
+impl MctsStrategy {
+    pub fn new() -> MctsStrategy {
+        MctsStrategy {
+            core: PlannerCore::new(),
+            tree: Tree::new(),
+            stats: Default::default(),
+            commands: Default::default(),
+            rng: XorShiftRng::from_seed([0x1337_5EED; 4]),
+
+            next_root: None,
+            planned_blob_count: Default::default(),
+            min_value: Default::default(),
+            max_value: Default::default(),
+        }
+    }
+
+    fn tick_impl(&mut self, world: &World) -> Command {
+        self.core.start_tick(world);
+        let on_plan = self.core.tracker.state.my_blobs.len() == self.planned_blob_count;
+        if self.commands.is_empty() || !on_plan {
+            self.commands.clear();
+            self.core.update_skips();
+            self.plan(on_plan);
+        }
+        let mut command = self.commands.pop_front().expect("no commands left");
+        if self.core.tracker.state.tick == 0 {
+            command.add_debug_message(format!("running mcts strategy version {}", VERSION));
+        }
+        #[cfg(feature = "debug")]
+        {
+            command.add_debug_message(format!("skips:\t{}", self.core.skips));
+            command.add_debug_message(format!("tree:\t{}", self.tree.len()));
+            command.add_debug_message(format!("visits:\t{}", self.stats[ROOT].visits));
+        }
+        command
+    }
+
+    fn plan(&mut self, keep_plan: bool) {
+        let simulator = self.core.simulator();
+        let root_state = self.core.tracker.state.clone();
+        match self.next_root.take() {
+            Some(next_root) if keep_plan => self.reroot(next_root, root_state),
+            _ => self.reset(root_state, &simulator.config),
+        }
+
+        let mut iterations = 0;
+        while self.core.within_budget(iterations) {
+            self.iterate(&simulator);
+            iterations += 1;
+        }
+
+        let best = self.tree.node(ROOT).children.iter().cloned().max_by_key(
+            |&child| self.stats[child].visits,
+        );
+        match best {
+            Some(best) => {
+                self.commands.extend(self.tree.node(best).commands.iter().cloned());
+                self.planned_blob_count = self.tree.node(best).state.my_blobs.len();
+                self.next_root = Some(best);
+            }
+            None => {
+                self.commands.push_back(Command::from_point(
+                    self.core.tracker.state.my_blobs[0].point(),
+                    &simulator.config,
+                ));
+                self.planned_blob_count = self.core.tracker.state.my_blobs.len();
+            }
+        }
+    }
+
+    fn reset(&mut self, root_state: State, config: &Config) {
+        self.stats.clear();
+        self.stats.push(Stats {
+            untried: actions(&root_state, config),
+            ..Default::default()
+        });
+        self.tree.reset(root_state);
+        self.min_value = f64::INFINITY;
+        self.max_value = f64::NEG_INFINITY;
+    }
+
+    // Keeps the statistics gathered below the child whose commands were just played.
+    fn reroot(&mut self, next_root: NodeId, root_state: State) {
+        let old_ids = self.tree.reroot(next_root, root_state);
+        let mut old_stats = mem::replace(&mut self.stats, Vec::with_capacity(old_ids.len()));
+        for id in old_ids {
+            let mut stats = mem::replace(&mut old_stats[id], Default::default());
+            stats.depth -= 1;
+            self.stats.push(stats);
+        }
+    }
+
+    fn iterate(&mut self, simulator: &Simulator) {
+        let mut node = ROOT;
+        while self.stats[node].untried.is_empty() && !self.tree.node(node).children.is_empty() {
+            node = self.select_child(node);
+        }
+
+        if !self.stats[node].untried.is_empty() {
+            let index = self.rng.gen_range(0, self.stats[node].untried.len());
+            let action = self.stats[node].untried.swap_remove(index);
+            let depth = self.stats[node].depth + 1;
+            let (commands, state) = {
+                let parent = &self.tree.node(node).state;
+                let commands = action_commands(action, parent, &simulator.config, self.core.skips);
+                let state = simulator.predict_states(parent, &commands);
+                (commands, state)
+            };
+            let untried = if depth < simulator.depth {
+                actions(&state, &simulator.config)
+            } else {
+                vec![]
+            };
+            node = self.tree.add_child(node, commands, state);
+            self.stats.push(Stats {
+                depth,
+                untried,
+                ..Default::default()
+            });
+        }
+
+        let value = rollout(
+            &mut self.rng,
+            simulator,
+            &self.core.scorer,
+            &self.core.tracker,
+            &self.tree.node(node).state,
+            self.stats[node].depth,
+            self.core.skips,
+        );
+        self.min_value = self.min_value.min(value);
+        self.max_value = self.max_value.max(value);
+        for id in self.tree.ancestors(node) {
+            self.stats[id].visits += 1;
+            self.stats[id].total_value += value;
+        }
+    }
+
+    fn select_child(&self, node: NodeId) -> NodeId {
+        let parent_visits = self.stats[node].visits as f64;
+        let range = self.max_value - self.min_value;
+        let uct = |child: NodeId| {
+            let stats = &self.stats[child];
+            let mean = stats.total_value / stats.visits as f64;
+            let exploitation = if range > 0.0 {
+                (mean - self.min_value) / range
+            } else {
+                0.5
+            };
+            exploitation + EXPLORATION_FACTOR * (parent_visits.ln() / stats.visits as f64).sqrt()
+        };
+        *self.tree
+            .node(node)
+            .children
+            .iter()
+            .max_by(|&&a, &&b| uct(a).partial_cmp(&uct(b)).expect("incomparable uct"))
+            .expect("select_child with no children")
+    }
+}
+
+impl Default for MctsStrategy {
+    fn default() -> MctsStrategy {
+        MctsStrategy::new()
+    }
+}
+
+impl Planner for MctsStrategy {
+    fn core_mut(&mut self) -> &mut PlannerCore {
+        &mut self.core
+    }
+}
+
+impl Strategy for MctsStrategy {
+    fn on_start(&mut self, config: &Config) {
+        self.core.tracker.set_config(config);
+    }
+
+    fn tick(&mut self, world: &World) -> Command {
+        if world.my_blobs.is_empty() {
+            return Default::default();
+        }
+        self.tick_impl(world)
+    }
+}
+
+fn actions(state: &State, config: &Config) -> Vec<Action> {
+    let me = match biggest_blob(state) {
+        Some(me) => me,
+        None => return vec![],
+    };
+    let mut splits = vec![false];
+    if me.can_split(state.my_blobs.len() as i64, config) {
+        splits.push(true);
+    }
+    let mut actions = vec![];
+    for split in splits {
+        for i in 0..ACTION_DIRECTIONS {
+            actions.push(Action {
+                angle: 2.0 * PI * i as f64 / ACTION_DIRECTIONS as f64,
+                split,
+            });
+        }
+    }
+    actions
+}
+
+fn action_commands(action: Action, state: &State, config: &Config, skips: i64) -> Vec<Command> {
+    let me = biggest_blob(state).expect("action_commands with no blobs");
+    let target = me.point() +
+        Point::from_polar(
+            me.vision_radius(state.my_blobs.len(), config) * COMMAND_DISTANCE_FACTOR,
+            me.angle() + action.angle,
+        );
+    (0..skips)
+        .map(|i| {
+            let mut command = Command::from_point(target, config);
+            if action.split && i == 0 {
+                command.set_split();
+            }
+            command
+        })
+        .collect()
+}
+
+fn rollout<R: Rng>(
+    rng: &mut R,
+    simulator: &Simulator,
+    scorer: &Scorer,
+    tracker: &WorldTracker,
+    state: &State,
+    depth: i64,
+    skips: i64,
+) -> Score {
+    let mut enemy_models: Vec<(PlayerId, &'static dyn EnemyModel)> = vec![];
+    for enemy in state.enemies.iter() {
+        if !enemy_models.iter().any(|&(id, _)| id == enemy.player_id()) {
+            let model = *rng.choose(&ENEMY_MODELS).expect("no enemy models");
+            enemy_models.push((enemy.player_id(), model));
+        }
+    }
+    let mut state = state.clone();
+    for _ in depth..simulator.depth {
+        let action = match rng.choose(&actions(&state, &simulator.config)) {
+            Some(&action) => action,
+            None => break,
+        };
+        let commands = action_commands(action, &state, &simulator.config, skips);
+        state = simulator.predict_states_with(&state, &commands, &enemy_models);
+    }
+    scorer.score(&state, tracker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITERATIONS: usize = 50;
+
+    fn world<'a>(tick: Tick, me: Player, config: &'a Config) -> World<'a> {
+        World {
+            tick,
+            my_blobs: vec![me],
+            food: (0..10)
+                .map(|i| {
+                    let x = 100.0 + 20.0 * i as f64;
+                    Food {
+                        id_: FoodId {
+                            x10: (x * 10.0) as u32,
+                            y10: 1500,
+                        },
+                        point_: Point::new(x, 150.0),
+                        r_: config.food_radius,
+                        m_: config.food_mass,
+                    }
+                })
+                .collect(),
+            ejections: vec![],
+            viruses: vec![],
+            enemies: vec![],
+            config,
+        }
+    }
+
+    fn planned(config: &Config) -> MctsStrategy {
+        let mut strategy = MctsStrategy::new();
+        strategy.set_budget(Budget::Paths(ITERATIONS));
+        strategy.on_start(config);
+        let me = Player::new(1, 0, Point::new(100.0, 100.0), 40.0, config);
+        strategy.tick(&world(0, me, config));
+        strategy
+    }
+
+    #[test]
+    fn plans_the_same_commands_for_a_fixed_seed() {
+        let config = Config::default();
+        let first = planned(&config);
+        let second = planned(&config);
+        assert_eq!(first.tree.len(), ITERATIONS + 1);
+        assert_eq!(first.stats[ROOT].visits, ITERATIONS as i64);
+        assert_eq!(first.commands.len() + 1, first.core.skips as usize);
+        let points = |strategy: &MctsStrategy| -> Vec<(f64, f64)> {
+            strategy.commands.iter().map(|command| (command.x(), command.y())).collect()
+        };
+        assert_eq!(points(&first), points(&second));
+    }
+
+    #[test]
+    fn keeps_the_subtree_of_the_played_child() {
+        let config = Config::default();
+        let mut strategy = planned(&config);
+        let next_root = strategy.next_root.expect("no child chosen");
+        let kept_visits = strategy.stats[next_root].visits;
+        let mut me = strategy.tree.node(next_root).state.my_blobs[0].clone();
+        let tick = strategy.commands.len() as Tick + 1;
+        strategy.commands.clear();
+        me.set_v(Point::zero());
+        strategy.tick(&world(tick, me, &config));
+        assert_eq!(strategy.stats[ROOT].visits, kept_visits + ITERATIONS as i64);
+        assert_eq!(strategy.stats[ROOT].depth, 0);
+        assert_eq!(strategy.tree.len(), strategy.stats.len());
+    }
+}
@@ -1,15 +1,34 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::iter;
 
 use models::*;
 use command::Command;
-use config::config;
+use config::Config;
+use strategy::enemy_model::{EnemyContext, EnemyModel, GREEDY_CHASE};
+use strategy::grid::Grid;
+
+pub type Points = i64;
+pub type Scores = HashMap<PlayerId, Points>;
+
+pub const SCORE_FOR_FOOD: Points = 1;
+pub const SCORE_FOR_PLAYER: Points = 10;
+pub const SCORE_FOR_BURST: Points = 2;
 
 #[derive(Debug)]
-pub struct Mechanic {
+pub struct Mechanic<'c> {
     pub state: State,
+    config: &'c Config,
     players: Vec<Player>,
     my_player_id: u32,
+    scores: Option<Scores>,
+    default_enemy_model: &'static dyn EnemyModel,
+    enemy_models: Vec<(PlayerId, &'static dyn EnemyModel)>,
+    splitting_enemies: Vec<PlayerId>,
+    predict_enemies: bool,
+    enemy_targets: Vec<(PlayerBlobId, Point)>,
+    food_grid: Option<&'c Grid>,
+    own_food_grid: Grid,
+    grid: Grid,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -17,49 +36,147 @@ pub struct State {
     pub tick: i64,
     pub my_blobs: Vec<Player>,
     pub enemies: Vec<Player>,
+    pub ejections: Vec<Ejection>,
+    pub viruses: Vec<Virus>,
     pub eaten_food: HashSet<FoodId>,
     pub eaten_ejections: HashSet<EjectionId>,
     pub eaten_viruses: HashSet<VirusId>,
 }
 
-impl Mechanic {
-    pub fn new(state: &State) -> Mechanic {
+impl<'c> Mechanic<'c> {
+    pub fn new(state: &State, config: &'c Config) -> Mechanic<'c> {
         let my_player_id = state.my_blobs.first().map_or(
             <PlayerId>::max_value(),
             |me| me.player_id(),
         );
         Mechanic {
             state: state.clone(),
+            config,
             players: Default::default(),
             my_player_id,
+            scores: None,
+            default_enemy_model: &GREEDY_CHASE,
+            enemy_models: Default::default(),
+            splitting_enemies: Default::default(),
+            predict_enemies: true,
+            enemy_targets: Default::default(),
+            food_grid: None,
+            own_food_grid: Grid::for_config(config),
+            grid: Grid::for_config(config),
+        }
+    }
+
+    pub fn with_scores(state: &State, config: &'c Config) -> Mechanic<'c> {
+        let mut mechanic = Mechanic::new(state, config);
+        mechanic.scores = Some(Default::default());
+        mechanic
+    }
+
+    pub fn config(&self) -> &'c Config {
+        self.config
+    }
+
+    pub fn scores(&self) -> Option<&Scores> {
+        self.scores.as_ref()
+    }
+
+    pub fn set_food_grid(&mut self, food_grid: &'c Grid) {
+        self.food_grid = Some(food_grid);
+    }
+
+    pub fn set_linear_scan(&mut self) {
+        self.food_grid = None;
+        self.own_food_grid = Grid::linear(self.config);
+        self.grid = Grid::linear(self.config);
+    }
+
+    pub fn set_default_enemy_model(&mut self, model: &'static dyn EnemyModel) {
+        self.default_enemy_model = model;
+    }
+
+    pub fn set_enemy_model(&mut self, player_id: PlayerId, model: &'static dyn EnemyModel) {
+        self.enemy_models.retain(|&(id, _)| id != player_id);
+        self.enemy_models.push((player_id, model));
+    }
+
+    pub fn set_enemy_models(&mut self, models: &[(PlayerId, &'static dyn EnemyModel)]) {
+        for &(player_id, model) in models.iter() {
+            self.set_enemy_model(player_id, model);
         }
     }
 
+    // Players without a command keep their velocity instead of following an enemy model.
+    pub fn set_predict_enemies(&mut self, predict_enemies: bool) {
+        self.predict_enemies = predict_enemies;
+    }
+
+    pub fn set_enemy_targets(&mut self, targets: &[(PlayerBlobId, Point)]) {
+        self.enemy_targets = targets.to_vec();
+    }
+
+    pub fn enemy_model(&self, player_id: PlayerId) -> &'static dyn EnemyModel {
+        self.enemy_models
+            .iter()
+            .find(|&&(id, _)| id == player_id)
+            .map_or(self.default_enemy_model, |&(_, model)| model)
+    }
+
     pub fn tick(
         &mut self,
         command: &Command,
         food: &[Food],
-        ejections: &[Ejection],
-        viruses: &[Virus],
     ) {
+        let my_player_id = self.my_player_id;
+        self.tick_with(
+            |player_id| if player_id == my_player_id {
+                Some(command)
+            } else {
+                None
+            },
+            food,
+        );
+    }
+
+    pub fn tick_all(
+        &mut self,
+        commands: &HashMap<PlayerId, Command>,
+        food: &[Food],
+    ) {
+        self.tick_with(
+            |player_id| commands.get(&player_id),
+            food,
+        );
+    }
+
+    fn tick_with<'a, C>(
+        &mut self,
+        commands: C,
+        food: &[Food],
+    ) where
+        C: Fn(PlayerId) -> Option<&'a Command>,
+    {
         self.players = iter::empty()
             .chain(self.state.my_blobs.drain(..))
             .chain(self.state.enemies.drain(..))
             .collect();
 
+        if self.food_grid.is_none() {
+            self.own_food_grid.rebuild(food);
+        }
+
         // Following vendor/miniaicups/agario/local_runner/mechanic.h
-        self.apply_strategies(command);
+        self.apply_strategies(&commands, food);
         self.state.tick += 1;
         self.move_moveables();
-        self.player_ejects();
-        self.player_splits(command);
+        self.player_ejects(&commands);
+        self.player_splits(&commands);
 
-        if self.state.tick % config().shrink_every_tick == 0 {
+        if self.state.tick % self.config.shrink_every_tick == 0 {
             self.shrink_players();
         }
-        self.eat_all(food, ejections);
+        self.eat_all(food);
         self.fuse_players();
-        self.burst_on_viruses(viruses);
+        self.burst_on_viruses();
 
         self.update_players_radius();
         self.update_scores();
@@ -73,81 +190,191 @@ impl Mechanic {
         self.state.enemies = enemies;
     }
 
-    fn apply_strategies(&mut self, my_command: &Command) {
+    fn apply_strategies<'a, C>(&mut self, commands: &C, food: &[Food])
+    where
+        C: Fn(PlayerId) -> Option<&'a Command>,
+    {
+        self.splitting_enemies.clear();
         for i in 0..self.players.len() {
-            if self.players[i].player_id() == self.my_player_id {
-                apply_direct(&mut self.players[i], my_command);
-            } else if let Some(target) = self.predict_enemy_target(&self.players[i]) {
-                apply_direct(&mut self.players[i], &Command::from_point(target));
+            let player_id = self.players[i].player_id();
+            if let Some(command) = commands(player_id) {
+                apply_direct(&mut self.players[i], command, self.config);
+            } else if let Some(&(_, target)) = self.enemy_targets.iter().find(|&&(id, _)| {
+                id == self.players[i].id()
+            })
+            {
+                // Only confident targets make it here, and they beat any model's guess.
+                let command = Command::from_point(target, self.config);
+                apply_direct(&mut self.players[i], &command, self.config);
+            } else if let Some(command) = self.predict_enemy_command(&self.players[i], food) {
+                apply_direct(&mut self.players[i], &command, self.config);
+                if command.split() && !self.splitting_enemies.contains(&player_id) {
+                    self.splitting_enemies.push(player_id);
+                }
             }
         }
     }
 
-    fn predict_enemy_target(&self, enemy: &Player) -> Option<Point> {
-        nearest_player(
-            enemy,
-            |me| me.player_id() == self.my_player_id && me.m() < enemy.m() && enemy.can_see(me, 1),
-            &self.players,
-        ).map(|i| self.players[i].point())
+    fn predict_enemy_command(&self, enemy: &Player, food: &[Food]) -> Option<Command> {
+        if !self.predict_enemies {
+            return None;
+        }
+        let context = EnemyContext {
+            my_player_id: self.my_player_id,
+            players: &self.players,
+            food,
+            food_grid: self.food_grid.unwrap_or(&self.own_food_grid),
+            eaten_food: &self.state.eaten_food,
+            config: self.config,
+        };
+        self.enemy_model(enemy.player_id()).command(enemy, &context)
     }
 
     fn move_moveables(&mut self) {
-        // TODO: Move ejections?
-        // TODO: Move viruses?
+        let config = self.config;
+        for ejection in self.state.ejections.iter_mut() {
+            let (point, v) = move_by_impulse(ejection.point(), ejection.v(), ejection.r(), config);
+            ejection.set_point(point);
+            ejection.set_v(v);
+        }
+
+        for virus in self.state.viruses.iter_mut() {
+            let (point, v) = move_by_impulse(virus.point(), virus.v(), virus.r(), config);
+            virus.set_point(point);
+            virus.set_v(v);
+        }
 
+        self.grid.rebuild(&self.players);
+        let mut found = vec![];
         for i in 0..self.players.len() {
-            for j in (i + 1)..self.players.len() {
+            self.grid.query(
+                self.players[i].point(),
+                self.players[i].r(),
+                &mut found,
+            );
+            for &j in found.iter().filter(|&&j| j > i) {
                 let (left, right) = self.players.as_mut_slice().split_at_mut(j);
                 let ref mut player = left[i];
                 let ref mut other = right[0];
                 if player.player_id() == other.player_id() {
-                    collision_calc(player, other);
+                    collision_calc(player, other, config);
                 }
             }
         }
 
         for player in self.players.iter_mut() {
-            move_player(player);
+            move_player(player, config);
         }
     }
 
-    fn player_ejects(&mut self) {
-        // TODO: Implement if adding command.eject.
+    fn player_ejects<'a, C>(&mut self, commands: &C)
+    where
+        C: Fn(PlayerId) -> Option<&'a Command>,
+    {
+        let config = self.config;
+        let ejects = |player: &Player| {
+            commands(player.player_id()).map_or(false, |command| command.eject()) &&
+                player.can_eject(config)
+        };
+        if !self.players.iter().any(&ejects) {
+            return;
+        }
+        let mut max_ejection_id = self.state
+            .ejections
+            .iter()
+            .map(|ejection| ejection.id())
+            .chain(self.state.eaten_ejections.iter().cloned())
+            .max()
+            .unwrap_or(0);
+        for player in self.players.iter_mut().filter(|player| ejects(player)) {
+            max_ejection_id += 1;
+            self.state.ejections.push(eject_now(player, max_ejection_id, config));
+        }
     }
 
-    fn player_splits(&mut self, command: &Command) {
-        if command.split() {
+    fn player_splits<'a, C>(&mut self, commands: &C)
+    where
+        C: Fn(PlayerId) -> Option<&'a Command>,
+    {
+        let mut player_ids: Vec<_> = self.players
+            .iter()
+            .map(|player| player.player_id())
+            .filter(|&player_id| {
+                commands(player_id).map_or(false, |command| command.split()) ||
+                    self.splitting_enemies.contains(&player_id)
+            })
+            .collect();
+        player_ids.sort_unstable();
+        player_ids.dedup();
+        for player_id in player_ids {
             let new_blobs = {
-                let my_player_id = self.my_player_id;
-                let ref mut my_blobs: Vec<_> = self.players
+                let ref mut fragments: Vec<_> = self.players
                     .iter_mut()
-                    .filter(|player| player.player_id() == my_player_id)
+                    .filter(|player| player.player_id() == player_id)
                     .collect();
-                split_fragments(my_blobs)
+                split_fragments(fragments, self.config)
             };
             self.players.extend(new_blobs);
         }
     }
 
     fn shrink_players(&mut self) {
+        let config = self.config;
         for player in self.players.iter_mut() {
-            if player.can_shrink() {
-                shrink_now(player);
+            if player.can_shrink(config) {
+                shrink_now(player, config);
             }
         }
     }
 
-    fn eat_all(&mut self, food: &[Food], ejections: &[Ejection]) {
-        eat_food(food, &mut self.state.eaten_food, &mut self.players);
+    fn eat_all(&mut self, food: &[Food]) {
         eat_food(
-            ejections,
+            food,
+            self.food_grid.unwrap_or(&self.own_food_grid),
+            &mut self.state.eaten_food,
+            &mut self.players,
+            SCORE_FOR_FOOD,
+            &mut self.scores,
+            self.config,
+        );
+        for ejection in self.state.ejections.iter() {
+            if self.state.eaten_ejections.contains(&ejection.id()) {
+                continue;
+            }
+            if let Some(i) = nearest(
+                ejection,
+                |virus| virus.can_eat_ejection(ejection),
+                self.state.viruses.iter().enumerate(),
+            )
+            {
+                virus_eat(&mut self.state.viruses[i], ejection);
+                self.state.eaten_ejections.insert(ejection.id());
+            }
+        }
+        self.grid.rebuild(&self.state.ejections);
+        eat_food(
+            &self.state.ejections,
+            &self.grid,
             &mut self.state.eaten_ejections,
             &mut self.players,
+            0,
+            &mut self.scores,
+            self.config,
+        );
+        let eaten_ejections = &self.state.eaten_ejections;
+        self.state.ejections.retain(|ejection| {
+            !eaten_ejections.contains(&ejection.id())
+        });
+        eat_players(
+            &mut self.players,
+            &mut self.grid,
+            &mut self.scores,
+            self.config,
         );
-        eat_players(&mut self.players);
     }
 
     fn fuse_players(&mut self) {
+        let config = self.config;
         const FUSED: PlayerId = <PlayerId>::max_value();
         self.players.sort_unstable_by(|a, b| {
             a.player_id().cmp(&b.player_id()).then_with(|| {
@@ -186,7 +413,7 @@ impl Mechanic {
                         }
                     }
                     if fused {
-                        update_by_mass(&mut fragments[i]);
+                        update_by_mass(&mut fragments[i], config);
                         idle = false;
                     }
                 }
@@ -204,42 +431,57 @@ impl Mechanic {
         }
     }
 
-    fn burst_on_viruses(&mut self, viruses: &[Virus]) {
-        // TODO: Burst other players.
-        let my_fragment_ids: Vec<_> = self.players
-            .iter()
-            .filter(|me| me.player_id() == self.my_player_id)
-            .map(|me| me.fragment_id())
-            .collect();
-        let mut fragment_count = my_fragment_ids.len() as i64;
-        let mut max_fragment_id = my_fragment_ids.into_iter().max().unwrap_or(0);
-        for virus in viruses.iter() {
+    fn burst_on_viruses(&mut self) {
+        let config = self.config;
+        let targets_count = self.players.len();
+        let mut burst_ids = vec![];
+        let mut found = vec![];
+        self.grid.rebuild(&self.players);
+        for virus in self.state.viruses.iter() {
+            self.grid.query(
+                virus.point(),
+                virus.r() * config.rad_hurt_factor,
+                &mut found,
+            );
             if let Some(i) = nearest_player(
                 virus,
-                |me| {
-                    me.player_id() == self.my_player_id && me.can_burst(fragment_count) &&
-                        virus.can_hurt(me)
+                |player| {
+                    virus.can_hurt(player, config) && !burst_ids.contains(&player.id()) &&
+                        player.can_burst(fragment_count(&self.players, player.player_id()), config)
                 },
-                &self.players,
+                &self.players[..targets_count],
+                found.iter().cloned(),
             )
             {
+                let player_id = self.players[i].player_id();
+                let fragment_count = fragment_count(&self.players, player_id);
+                let mut max_fragment_id = self.players
+                    .iter()
+                    .filter(|player| player.player_id() == player_id)
+                    .map(|player| player.fragment_id())
+                    .max()
+                    .unwrap_or(0);
                 let new_blobs = {
-                    let ref mut me = self.players[i];
-                    // TODO: targets.removeAll(player);
-                    burst_on(me, virus);
-                    burst_now(me, fragment_count, &mut max_fragment_id)
+                    let ref mut player = self.players[i];
+                    burst_on(player, virus, config);
+                    burst_now(player, fragment_count, &mut max_fragment_id, config)
                 };
-                fragment_count += new_blobs.len() as i64;
-                // TODO: Don't burst new_blobs on this tick.
+                burst_ids.push(self.players[i].id());
                 self.players.extend(new_blobs);
                 self.state.eaten_viruses.insert(virus.id());
+                add_score(&mut self.scores, player_id, SCORE_FOR_BURST);
             }
         }
+        let eaten_viruses = &self.state.eaten_viruses;
+        self.state.viruses.retain(
+            |virus| !eaten_viruses.contains(&virus.id()),
+        );
     }
 
     fn update_players_radius(&mut self) {
+        let config = self.config;
         for player in self.players.iter_mut() {
-            update_by_mass(player);
+            update_by_mass(player, config);
         }
     }
 
@@ -248,26 +490,45 @@ impl Mechanic {
     }
 
     fn split_viruses(&mut self) {
-        // TODO: Implement this if ejections are implemented.
+        let config = self.config;
+        if !self.state.viruses.iter().any(|virus| virus.can_split(config)) {
+            return;
+        }
+        let mut max_virus_id = self.state
+            .viruses
+            .iter()
+            .map(|virus| virus.id())
+            .chain(self.state.eaten_viruses.iter().cloned())
+            .max()
+            .unwrap_or(0);
+        let mut new_viruses = vec![];
+        for virus in self.state.viruses.iter_mut().filter(
+            |virus| virus.can_split(config),
+        )
+        {
+            max_virus_id += 1;
+            new_viruses.push(split_virus_now(virus, max_virus_id, config));
+        }
+        self.state.viruses.extend(new_viruses);
     }
 }
 
-fn apply_direct(player: &mut Player, command: &Command) {
+pub fn apply_direct(player: &mut Player, command: &Command, config: &Config) {
     if player.is_fast() {
         return;
     }
-    let max_speed = player.max_speed();
+    let max_speed = player.max_speed(config);
     let target_v = (command.point() - player.point()).with_length(max_speed);
-    let v = player.v() + (target_v - player.v()) * (config().inertion_factor / player.m());
+    let v = player.v() + (target_v - player.v()) * (config.inertion_factor / player.m());
     let v = v.limit_length(max_speed);
     player.set_v(v);
 }
 
-fn move_player(player: &mut Player) {
+pub fn move_player(player: &mut Player, config: &Config) {
     let mut v = player.v();
 
     let min_x = player.r();
-    let max_x = config().game_width as f64 - player.r();
+    let max_x = config.game_width as f64 - player.r();
     let mut new_x = player.point().x + player.v().x;
     if !(min_x <= new_x && new_x <= max_x) {
         v.x = 0.0;
@@ -275,7 +536,7 @@ fn move_player(player: &mut Player) {
     }
 
     let min_y = player.r();
-    let max_y = config().game_height as f64 - player.r();
+    let max_y = config.game_height as f64 - player.r();
     let mut new_y = player.point().y + player.v().y;
     if !(min_y <= new_y && new_y <= max_y) {
         v.y = 0.0;
@@ -286,7 +547,7 @@ fn move_player(player: &mut Player) {
     player.set_v(v);
 
     if player.is_fast() {
-        apply_viscosity(player);
+        apply_viscosity(player, config);
     }
 
     if player.ttf() > 0 {
@@ -295,11 +556,29 @@ fn move_player(player: &mut Player) {
     }
 }
 
-fn apply_viscosity(player: &mut Player) {
-    let usual_speed = player.max_speed();
+fn move_by_impulse(point: Point, v: Point, r: f64, config: &Config) -> (Point, Point) {
+    if v.x == 0.0 && v.y == 0.0 {
+        return (point, v);
+    }
+
+    let x = (point.x + v.x).max(r).min(config.game_width as f64 - r);
+    let y = (point.y + v.y).max(r).min(config.game_height as f64 - r);
+
+    let speed = (v.length() - config.viscosity).max(0.0);
+    (Point::new(x, y), v.with_length(speed))
+}
+
+// The impulse left after moving by the given displacement, as move_by_impulse would leave it.
+pub fn observed_impulse(displacement: Point, config: &Config) -> Point {
+    let speed = (displacement.length() - config.viscosity).max(0.0);
+    displacement.with_length(speed)
+}
+
+fn apply_viscosity(player: &mut Player, config: &Config) {
+    let usual_speed = player.max_speed(config);
     let mut speed = player.speed();
-    if speed - config().viscosity > usual_speed {
-        speed -= config().viscosity;
+    if speed - config.viscosity > usual_speed {
+        speed -= config.viscosity;
     } else {
         speed = usual_speed;
         player.set_fast(false);
@@ -308,7 +587,7 @@ fn apply_viscosity(player: &mut Player) {
     player.set_v(v);
 }
 
-fn collision_calc(a: &mut Player, b: &mut Player) {
+fn collision_calc(a: &mut Player, b: &mut Player, config: &Config) {
     if a.is_fast() || b.is_fast() {
         return;
     }
@@ -325,7 +604,7 @@ fn collision_calc(a: &mut Player, b: &mut Player) {
         return;
     }
     let collision_vector = collision_vector.unit();
-    let collision_force = (1.0 - qdist.sqrt() / sum_r).powi(2) * config().collision_power;
+    let collision_force = (1.0 - qdist.sqrt() / sum_r).powi(2) * config.collision_power;
 
     let sum_m = a.m() + b.m();
     let v = a.v() + collision_vector * (collision_force * b.m() / sum_m);
@@ -334,7 +613,7 @@ fn collision_calc(a: &mut Player, b: &mut Player) {
     b.set_v(v);
 }
 
-fn split_fragments(fragments: &mut [&mut Player]) -> Vec<Player> {
+fn split_fragments(fragments: &mut [&mut Player], config: &Config) -> Vec<Player> {
     fragments.sort_unstable_by(|a, b| {
         a.m()
             .partial_cmp(&b.m())
@@ -350,17 +629,17 @@ fn split_fragments(fragments: &mut [&mut Player]) -> Vec<Player> {
         .unwrap_or(0);
     let mut new_blobs = vec![];
     for player in fragments.iter_mut() {
-        if player.can_split(fragment_count) {
-            new_blobs.push(split_now(player, &mut max_fragment_id));
+        if player.can_split(fragment_count, config) {
+            new_blobs.push(split_now(player, &mut max_fragment_id, config));
             fragment_count += 1;
         }
     }
     new_blobs
 }
 
-fn split_now(player: &mut Player, max_fragment_id: &mut FragmentId) -> Player {
+fn split_now(player: &mut Player, max_fragment_id: &mut FragmentId, config: &Config) -> Player {
     let new_m = player.m() / 2.0;
-    let new_r = Player::mass_to_radius(new_m);
+    let new_r = Player::mass_to_radius(new_m, config);
 
     let new_blob = Player {
         id_: PlayerBlobId {
@@ -370,45 +649,96 @@ fn split_now(player: &mut Player, max_fragment_id: &mut FragmentId) -> Player {
         point_: player.point(),
         m_: new_m,
         r_: new_r,
-        v_: Point::from_polar(config().split_start_speed, player.angle()),
+        v_: Point::from_polar(config.split_start_speed, player.angle()),
         is_fast_: true,
-        ttf_: config().ticks_til_fusion,
+        ttf_: config.ticks_til_fusion,
     };
 
     player.set_fragment_id(*max_fragment_id + 2);
     player.set_m(new_m);
     player.set_r(new_r);
-    player.set_ttf(config().ticks_til_fusion);
+    player.set_ttf(config.ticks_til_fusion);
 
     *max_fragment_id = player.fragment_id();
     new_blob
 }
 
-fn shrink_now(player: &mut Player) {
-    let new_m = player.m() - (player.m() - config().min_shrink_mass) * config().shrink_factor;
+fn eject_now(player: &mut Player, ejection_id: EjectionId, config: &Config) -> Ejection {
+    let angle = player.angle();
+    let ejection = Ejection {
+        id_: ejection_id,
+        point_: player.point() + Point::from_polar(player.r() + 1.0, angle),
+        r_: config.ejection_radius,
+        m_: config.ejection_mass,
+        v_: Point::from_polar(config.ejection_start_speed, angle),
+    };
+
+    let new_m = player.m() - config.ejection_mass;
+    player.set_m(new_m);
+    player.set_r(Player::mass_to_radius(new_m, config));
+    ejection
+}
+
+fn shrink_now(player: &mut Player, config: &Config) {
+    let new_m = player.m() - (player.m() - config.min_shrink_mass) * config.shrink_factor;
     player.set_m(new_m);
-    player.set_r(Player::mass_to_radius(new_m));
+    player.set_r(Player::mass_to_radius(new_m, config));
 }
 
-fn eat_food<F: Blob>(food: &[F], eaten: &mut HashSet<F::Id>, players: &mut [Player]) {
-    for blob in food.iter() {
+fn eat_food<F: Blob>(
+    food: &[F],
+    food_grid: &Grid,
+    eaten: &mut HashSet<F::Id>,
+    players: &mut [Player],
+    points: Points,
+    scores: &mut Option<Scores>,
+    config: &Config,
+) {
+    if food.is_empty() {
+        return;
+    }
+    let mut candidates = vec![];
+    let mut found = vec![];
+    for player in players.iter() {
+        food_grid.query(player.point(), player.r(), &mut found);
+        candidates.extend_from_slice(&found);
+    }
+    candidates.sort_unstable();
+    candidates.dedup();
+    for blob in candidates.into_iter().map(|k| &food[k]) {
         if eaten.contains(&blob.id()) {
             continue;
         }
-        if let Some(i) = nearest_player(blob, |player| player.can_eat_blob(blob), players.iter()) {
+        if let Some(i) = deepest_eater(
+            blob,
+            |player| player.can_eat_blob(blob, config),
+            players,
+            0..players.len(),
+        )
+        {
             player_eat(&mut players[i], blob);
             eaten.insert(blob.id());
+            add_score(scores, players[i].player_id(), points);
         }
     }
 }
 
-fn eat_players(players: &mut Vec<Player>) {
+fn eat_players(
+    players: &mut Vec<Player>,
+    grid: &mut Grid,
+    scores: &mut Option<Scores>,
+    config: &Config,
+) {
+    grid.rebuild(players);
+    let mut found = vec![];
     let mut i = 0;
     while i < players.len() {
-        if let Some(j) = nearest_player(
+        grid.query(players[i].point(), players[i].r(), &mut found);
+        if let Some(j) = deepest_eater(
             &players[i],
-            |eater| eater.can_eat_player(&players[i]),
-            players.iter(),
+            |eater| eater.can_eat_player(&players[i], config),
+            players,
+            found.iter().cloned(),
         )
         {
             {
@@ -420,32 +750,96 @@ fn eat_players(players: &mut Vec<Player>) {
                     (&right[0], &mut left[j])
                 };
                 player_eat(eater, player);
+                add_score(scores, eater.player_id(), SCORE_FOR_PLAYER);
             }
             players.swap_remove(i);
+            grid.rebuild(players);
         } else {
             i += 1;
         }
     }
 }
 
+fn add_score(scores: &mut Option<Scores>, player_id: PlayerId, points: Points) {
+    if let Some(ref mut scores) = *scores {
+        *scores.entry(player_id).or_insert(0) += points;
+    }
+}
+
+fn fragment_count(players: &[Player], player_id: PlayerId) -> i64 {
+    players
+        .iter()
+        .filter(|player| player.player_id() == player_id)
+        .count() as i64
+}
+
+fn virus_eat(virus: &mut Virus, ejection: &Ejection) {
+    let new_m = virus.m() + ejection.m();
+    virus.set_m(new_m);
+    virus.set_split_angle(ejection.v().angle());
+}
+
 fn player_eat<F: Blob>(player: &mut Player, food: &F) {
     let new_m = player.m() + food.m();
     player.set_m(new_m);
 }
 
-fn nearest_player<'a, T, P, U>(target: &T, predicate: P, players: U) -> Option<usize>
+fn nearest_player<T, P, I>(
+    target: &T,
+    predicate: P,
+    players: &[Player],
+    candidates: I,
+) -> Option<usize>
 where
     T: HasPoint,
     P: Fn(&Player) -> bool,
-    U: IntoIterator<Item = &'a Player>,
+    I: IntoIterator<Item = usize>,
+{
+    nearest(
+        target,
+        predicate,
+        candidates.into_iter().map(|i| (i, &players[i])),
+    )
+}
+
+// Like the server, prefers the eater that the target overlaps the deepest, and the first one on
+// ties.
+fn deepest_eater<T, P, I>(
+    target: &T,
+    predicate: P,
+    players: &[Player],
+    candidates: I,
+) -> Option<usize>
+where
+    T: HasPoint,
+    P: Fn(&Player) -> bool,
+    I: IntoIterator<Item = usize>,
 {
     let target = target.point();
-    players
+    candidates
         .into_iter()
-        .enumerate()
+        .map(|i| (i, &players[i]))
         .filter(|&(_, player)| predicate(player))
         .min_by(|&(_, a), &(_, b)| {
-            // TODO: Incorporate depth calculation.
+            let a_depth = a.r() - a.point().dist(target);
+            let b_depth = b.r() - b.point().dist(target);
+            b_depth.partial_cmp(&a_depth).expect("incomparable depths")
+        })
+        .map(|(i, _)| i)
+}
+
+fn nearest<'a, T, B, P, U>(target: &T, predicate: P, blobs: U) -> Option<usize>
+where
+    T: HasPoint,
+    B: 'a + HasPoint,
+    P: Fn(&B) -> bool,
+    U: IntoIterator<Item = (usize, &'a B)>,
+{
+    let target = target.point();
+    blobs
+        .into_iter()
+        .filter(|&(_, blob)| predicate(blob))
+        .min_by(|&(_, a), &(_, b)| {
             a.point()
                 .qdist(target)
                 .partial_cmp(&b.point().qdist(target))
@@ -454,21 +848,21 @@ where
         .map(|(i, _)| i)
 }
 
-fn update_by_mass(player: &mut Player) {
-    let r = Player::mass_to_radius(player.m());
+fn update_by_mass(player: &mut Player, config: &Config) {
+    let r = Player::mass_to_radius(player.m(), config);
     player.set_r(r);
 
     if !player.is_fast() {
-        let v = player.v().limit_length(player.max_speed());
+        let v = player.v().limit_length(player.max_speed(config));
         player.set_v(v);
     }
 
     let x = player.point().x.max(player.r()).min(
-        config().game_width as
+        config.game_width as
             f64 - player.r(),
     );
     let y = player.point().y.max(player.r()).min(
-        config().game_height as
+        config.game_height as
             f64 - player.r(),
     );
     player.set_point(Point::new(x, y));
@@ -489,13 +883,28 @@ fn fusion(player: &mut Player, other: &Player) {
     player.set_m(m);
 }
 
-fn burst_on(player: &mut Player, virus: &Virus) {
-    let speed = player.speed().min(player.max_speed());
+fn split_virus_now(virus: &mut Virus, virus_id: VirusId, config: &Config) -> Virus {
+    let angle = virus.split_angle();
+    let new_virus = Virus {
+        id_: virus_id,
+        point_: virus.point() + Point::from_polar(virus.r() * 2.0, angle),
+        r_: virus.r(),
+        m_: config.virus_mass,
+        v_: Point::from_polar(config.virus_split_speed, angle),
+        split_angle_: angle,
+    };
+
+    virus.set_m(config.virus_mass);
+    new_virus
+}
+
+fn burst_on(player: &mut Player, virus: &Virus, config: &Config) {
+    let speed = player.speed().min(player.max_speed(config));
     let angle = (player.point() - virus.point()).angle();
     let v = Point::from_polar(speed, angle);
     player.set_v(v);
 
-    let m = player.m() + config().burst_bonus;
+    let m = player.m() + config.burst_bonus;
     player.set_m(m);
 }
 
@@ -503,17 +912,18 @@ fn burst_now(
     player: &mut Player,
     fragment_count: i64,
     max_fragment_id: &mut FragmentId,
+    config: &Config,
 ) -> Vec<Player> {
-    let new_fragment_count = ((player.m() / config().min_burst_mass).floor() as i64 - 1)
-        .min(Player::rest_fragment_count(fragment_count));
+    let new_fragment_count = ((player.m() / config.min_burst_mass).floor() as i64 - 1)
+        .min(Player::rest_fragment_count(fragment_count, config));
 
     let new_m = player.m() / (new_fragment_count + 1) as Mass;
-    let new_r = Player::mass_to_radius(new_m);
+    let new_r = Player::mass_to_radius(new_m, config);
 
     let new_blobs = (0..new_fragment_count)
         .map(|i| {
-            let angle = player.angle() - config().burst_angle_spectrum / 2.0 +
-                i as f64 * config().burst_angle_spectrum / new_fragment_count as f64;
+            let angle = player.angle() - config.burst_angle_spectrum / 2.0 +
+                i as f64 * config.burst_angle_spectrum / new_fragment_count as f64;
             Player {
                 id_: PlayerBlobId {
                     player_id: player.player_id(),
@@ -522,16 +932,16 @@ fn burst_now(
                 point_: player.point(),
                 m_: new_m,
                 r_: new_r,
-                v_: Point::from_polar(config().burst_start_speed, angle),
+                v_: Point::from_polar(config.burst_start_speed, angle),
                 is_fast_: true,
-                ttf_: config().ticks_til_fusion,
+                ttf_: config.ticks_til_fusion,
             }
         })
         .collect();
 
     let v = Point::from_polar(
-        config().burst_start_speed,
-        player.angle() + config().burst_angle_spectrum / 2.0,
+        config.burst_start_speed,
+        player.angle() + config.burst_angle_spectrum / 2.0,
     );
     player.set_v(v);
     player.set_fast(true);
@@ -539,8 +949,100 @@ fn burst_now(
     player.set_fragment_id(*max_fragment_id + 1 + new_fragment_count as FragmentId);
     player.set_m(new_m);
     player.set_r(new_r);
-    player.set_ttf(config().ticks_til_fusion);
+    player.set_ttf(config.ticks_til_fusion);
 
     *max_fragment_id = player.fragment_id();
     new_blobs
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn virus(point: Point, m: Mass, config: &Config) -> Virus {
+        Virus {
+            id_: 1,
+            point_: point,
+            r_: config.virus_radius,
+            m_: m,
+            v_: Point::zero(),
+            split_angle_: 0.0,
+        }
+    }
+
+    #[test]
+    fn feeds_and_splits_viruses() {
+        let config = Config::default();
+        let mut state = State::default();
+        let m = config.virus_split_mass - config.ejection_mass / 2.0;
+        state.viruses.push(virus(Point::new(500.0, 500.0), m, &config));
+        state.ejections.push(Ejection {
+            id_: 1,
+            point_: Point::new(480.0, 500.0),
+            r_: config.ejection_radius,
+            m_: config.ejection_mass,
+            v_: Point::new(config.ejection_start_speed, 0.0),
+        });
+        let mut mechanic = Mechanic::new(&state, &config);
+        mechanic.tick(&Command::new(), &[]);
+        assert!(mechanic.state.ejections.is_empty());
+        let viruses = &mechanic.state.viruses;
+        assert_eq!(viruses.len(), 2);
+        assert_eq!(viruses[0].m(), config.virus_mass);
+        assert_eq!(viruses[1].m(), config.virus_mass);
+        assert!(viruses[1].point().x > viruses[0].point().x);
+        assert!(viruses[1].v().dist(Point::new(config.virus_split_speed, 0.0)) < 1e-9);
+    }
+
+    #[test]
+    fn bursts_players_on_viruses() {
+        let config = Config::default();
+        let mut state = State::default();
+        let m = 300.0;
+        state.my_blobs.push(Player::new(1, 0, Point::new(500.0, 500.0), m, &config));
+        state.viruses.push(virus(Point::new(530.0, 500.0), config.virus_mass, &config));
+        let mut mechanic = Mechanic::new(&state, &config);
+        mechanic.tick(&Command::from_point(Point::new(400.0, 500.0), &config), &[]);
+        assert!(mechanic.state.viruses.is_empty());
+        let my_blobs = &mechanic.state.my_blobs;
+        let fragments = ((m + config.burst_bonus) / config.min_burst_mass).floor() as usize;
+        assert_eq!(my_blobs.len(), fragments);
+        assert!(my_blobs.iter().all(|blob| blob.is_fast()));
+        assert!(my_blobs.iter().all(|blob| blob.ttf() == config.ticks_til_fusion));
+        let total_m: Mass = my_blobs.iter().map(|blob| blob.m()).sum();
+        assert!((total_m - m - config.burst_bonus).abs() < 1e-9);
+    }
+
+    #[test]
+    fn prefers_the_deepest_eater() {
+        let config = Config::default();
+        let food = Point::new(500.0, 500.0);
+        let players = vec![
+            Player::new(1, 0, Point::new(505.0, 500.0), 20.0, &config),
+            Player::new(2, 0, Point::new(520.0, 500.0), 200.0, &config),
+        ];
+        assert_eq!(nearest_player(&food, |_| true, &players, 0..2), Some(0));
+        assert_eq!(deepest_eater(&food, |_| true, &players, 0..2), Some(1));
+        assert_eq!(deepest_eater(&food, |player| player.m() < 100.0, &players, 0..2), Some(0));
+    }
+
+    #[test]
+    fn prefers_enemy_target_over_model() {
+        let config = Config::default();
+        let mut state = State::default();
+        state.my_blobs.push(Player::new(1, 0, Point::new(450.0, 500.0), 20.0, &config));
+        state.enemies.push(Player::new(2, 0, Point::new(500.0, 500.0), 100.0, &config));
+        let enemy_id = state.enemies[0].id();
+        let stay = Command::from_point(Point::new(450.0, 500.0), &config);
+
+        let mut mechanic = Mechanic::new(&state, &config);
+        mechanic.tick(&stay, &[]);
+        assert!(mechanic.state.enemies[0].v().x < 0.0);
+
+        let mut mechanic = Mechanic::new(&state, &config);
+        mechanic.set_enemy_targets(&[(enemy_id, Point::new(500.0, 900.0))]);
+        mechanic.tick(&stay, &[]);
+        let v = mechanic.state.enemies[0].v();
+        assert!(v.y > 0.0 && v.x.abs() < 1e-9, "{:?}", v);
+    }
+}
@@ -1,12 +1,31 @@
-pub use self::my_strategy::MyStrategy;
-pub use self::strategy::{Strategy, Tick};
+pub use self::evolution_strategy::EvolutionStrategy;
+pub use self::mcts_strategy::MctsStrategy;
+pub use self::my_strategy::{MyStrategy, Budget};
+pub use self::params::SearchParams;
+pub use self::planner::Planner;
+pub use self::scoring::{Scorer, ScoreWeights};
+pub use self::strategy::{Strategy, Tick, World};
 #[cfg(feature = "debug")]
 pub use self::timing_wrapper::TimingWrapper;
 pub use command::*;
 pub use models::*;
 
-mod mechanic;
+pub mod enemy_model;
+mod evolution_strategy;
+pub mod food_map;
+pub mod fragment_tracker;
+pub mod grid;
+pub mod mechanic;
+mod mcts_strategy;
 mod my_strategy;
+mod opponent_tracker;
+pub mod params;
+mod planner;
+pub mod scoring;
+mod simulator;
 mod strategy;
 #[cfg(feature = "debug")]
 mod timing_wrapper;
+pub mod tree;
+pub mod velocity_filter;
+mod world_tracker;
@@ -1,31 +1,31 @@
-use std::cell::RefCell;
-use std::collections::{HashMap, VecDeque};
+use std::collections::VecDeque;
 use std::f64::consts::PI;
-use std::rc::{Rc, Weak};
-use std::time::{Instant, Duration};
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+#[cfg(feature = "parallel")]
+use std::thread;
+use std::time::Instant;
 
 use rand::{Rng, SeedableRng, XorShiftRng};
 
-use config::config;
+use config::Config;
 use strategy::*;
-use strategy::mechanic::{Mechanic, State};
+use strategy::enemy_model::EnemyModel;
+use strategy::mechanic::State;
+use strategy::planner::PlannerCore;
+use strategy::scoring::Score;
+#[cfg(feature = "debug")]
+use strategy::scoring::TERMS;
+use strategy::simulator::*;
+use strategy::tree::{NodeId, Tree, ROOT};
+#[cfg(feature = "debug")]
+use strategy::world_tracker::GHOST_VISIBILITY_FACTOR;
 use version::VERSION;
 
-const AVG_TICK_TIME_SECS: f64 = 600.0 / 25000.0;
-const MIN_SKIPS: i64 = 5;
-const SIMULATION_DEPTH: i64 = 7;
 const COMMAND_DISTANCE_FACTOR: f64 = 2.0;
-
-const GHOST_TICKS: i64 = 50;
-const GHOST_VISIBILITY_FACTOR: f64 = 0.80;
-const GHOST_TTF_FACTOR: f64 = 0.5;
-
-const SPEED_REWARD_FACTOR: f64 = 0.01;
-const DANGER_PENALTY_FACTOR: f64 = -300.0;
-const SAFETY_MARGIN_FACTOR: f64 = 7.0;
-const SAFETY_MARGIN_PENALTY: f64 = -5.0;
-const SMALL_BLOB_PENALTY: f64 = -10.0;
-const MAX_SMALL_BLOB_MASS: f64 = 85.0;
+const GOAL_TARGETS: usize = 3;
+#[cfg(feature = "parallel")]
+const WORKER_THREADS: usize = 4;
 
 lazy_static! {
     static ref DISCOVERY_ANGLES: Vec<Angle> = {
@@ -36,152 +36,83 @@ lazy_static! {
 
 #[derive(Debug)]
 pub struct MyStrategy {
-    root: SharedNode,
-    next_root: SharedNode,
+    tree: Tree,
+    next_root: NodeId,
     commands: VecDeque<Command>,
-    ghost_enemies: HashMap<PlayerBlobId, Ghost>,
     rng: XorShiftRng,
-
-    state: State,
-    food: Vec<Food>,
-    ejections: Vec<Ejection>,
-    viruses: Vec<Virus>,
-
-    tick_start_time: Instant,
-    skips: i64,
-    target: SharedNode,
+    core: PlannerCore,
+    target: NodeId,
 
     paths_seen: i64,
     num_paths: i64,
 }
 
-#[derive(Debug, Default)]
-struct Node {
-    state: State,
-    commands: Vec<Command>,
-    parent: Weak<RefCell<Node>>,
-    children: Vec<SharedNode>,
-}
-
-type SharedNode = Rc<RefCell<Node>>;
-type Score = f64;
-
-#[derive(Debug)]
-struct Ghost {
-    player: Player,
-    last_seen: Tick,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    Time,
+    Paths(usize),
 }
 
 impl MyStrategy {
     pub fn new() -> MyStrategy {
         MyStrategy {
-            root: Default::default(),
-            next_root: Default::default(),
+            tree: Tree::new(),
+            next_root: ROOT,
             commands: Default::default(),
-            ghost_enemies: Default::default(),
             rng: XorShiftRng::from_seed([0x1337_5EED; 4]),
-
-            state: Default::default(),
-            food: Default::default(),
-            ejections: Default::default(),
-            viruses: Default::default(),
-
-            tick_start_time: Instant::now(),
-            skips: Default::default(),
-            target: Default::default(),
+            core: PlannerCore::new(),
+            target: ROOT,
 
             paths_seen: Default::default(),
             num_paths: Default::default(),
         }
     }
 
-    fn node_score(&self, node: &SharedNode) -> Score {
-        let ref state = node.borrow().state;
-        state
-            .my_blobs
-            .iter()
-            .map(|me| self.blob_score(me, state))
-            .sum()
+    pub fn with_enemy_model(enemy_model: &'static dyn EnemyModel) -> MyStrategy {
+        let mut strategy = MyStrategy::new();
+        strategy.core.enemy_model = enemy_model;
+        strategy
     }
 
-    fn blob_score(&self, me: &Player, state: &State) -> Score {
-        let mut score = 0.0;
-        score += me.m();
-
-        score += me.speed() * SPEED_REWARD_FACTOR;
-
-        if me.m() <= MAX_SMALL_BLOB_MASS {
-            score += SMALL_BLOB_PENALTY;
-        }
-
-        for enemy in state.enemies.iter() {
-            if enemy.m() > me.m() {
-                let mut speed = enemy.max_speed();
-                if enemy.m() > me.m() * 2.0 {
-                    speed = speed.max(config().split_start_speed);
-                }
-                let dist = me.point().dist(enemy.point());
-                score += DANGER_PENALTY_FACTOR / (dist / speed).max(1.0).min(3.0);
-            }
-        }
-
-        // TODO: global goal.
-
-        let safety_margin = me.r() * SAFETY_MARGIN_FACTOR;
-        if me.x() < safety_margin || me.x() > config().game_width as f64 - safety_margin {
-            score += SAFETY_MARGIN_PENALTY;
-        }
-        if me.y() < safety_margin || me.y() > config().game_height as f64 - safety_margin {
-            score += SAFETY_MARGIN_PENALTY;
-        }
-
-        score
+    fn node_score(&self, node: NodeId) -> Score {
+        self.core.scorer.score(&self.tree.node(node).state, &self.core.tracker)
     }
 
-    fn tick_impl(
-        &mut self,
-        tick: Tick,
-        my_blobs: Vec<Player>,
-        food: Vec<Food>,
-        ejections: Vec<Ejection>,
-        viruses: Vec<Virus>,
-        enemies: Vec<Player>,
-    ) -> Command {
-        self.tick_start_time = Instant::now();
-        self.food = food;
-        self.ejections = ejections;
-        self.viruses = viruses;
-        self.state.tick = tick;
-        self.state.my_blobs = my_blobs;
-        self.state.eaten_food = Default::default();
-        self.state.eaten_ejections = Default::default();
-        self.state.eaten_viruses = Default::default();
-        self.update_enemies(enemies);
-        if self.commands.is_empty() ||
-            self.state.my_blobs.len() != self.target.borrow().state.my_blobs.len() ||
-            self.state.enemies.len() != self.target.borrow().state.enemies.len()
-        {
+    fn tick_impl(&mut self, world: &World) -> Command {
+        self.core.start_tick(world);
+        let on_plan = self.is_on_plan();
+        if self.commands.is_empty() || !on_plan {
             self.commands.clear();
-            self.update_skips();
-            self.add_commands();
+            self.core.update_skips();
+            self.add_commands(on_plan);
         }
         let mut command = self.commands.pop_front().expect("no commands left");
-        if self.state.tick == 0 {
+        if self.core.tracker.state.tick == 0 {
             command.add_debug_message(format!("running my strategy version {}", VERSION));
         }
         #[cfg(feature = "debug")] self.debug(&mut command);
         command
     }
 
-    fn add_commands(&mut self) {
-        self.root = Default::default();
-        self.root.borrow_mut().state = self.state.clone();
+    fn is_on_plan(&self) -> bool {
+        self.tree.get(self.target).map_or(false, |target| {
+            self.core.tracker.state.my_blobs.len() == target.state.my_blobs.len() &&
+                self.core.tracker.state.enemies.len() == target.state.enemies.len()
+        })
+    }
+
+    fn add_commands(&mut self, keep_plan: bool) {
+        if keep_plan && self.next_root != ROOT {
+            self.tree.reroot(self.next_root, self.core.tracker.state.clone());
+        } else {
+            self.tree.reset(self.core.tracker.state.clone());
+        }
         self.add_nodes();
 
-        self.target = find_nodes(&self.root)
-            .into_iter()
-            .filter(|node| !Rc::ptr_eq(&node, &self.root))
-            .max_by(|a, b| {
+        self.target = self.tree
+            .ids()
+            .filter(|&node| node != ROOT)
+            .max_by(|&a, &b| {
                 self.node_score(a).partial_cmp(&self.node_score(b)).expect(
                     "incomparable scores",
                 )
@@ -189,11 +120,11 @@ impl MyStrategy {
             .expect("no nodes found");
         self.next_root = self.next_root();
         self.commands.extend(
-            self.next_root
-                .borrow()
+            self.tree
+                .node(self.next_root)
                 .commands
                 .iter()
-                .take(self.skips as usize)
+                .take(self.core.skips as usize)
                 .cloned(),
         );
     }
@@ -203,144 +134,110 @@ impl MyStrategy {
         self.rng.shuffle(&mut paths);
         #[cfg(feature = "debug")]
         {
-            self.paths_seen = 0;
             self.num_paths = paths.len() as i64;
         }
-        for (i, path) in paths.into_iter().enumerate() {
-            let time_budget = AVG_TICK_TIME_SECS * self.skips as f64;
-            let elapsed = duration_to_secs(self.tick_start_time.elapsed());
-            if i != 0 && elapsed * (i + 1) as f64 / i as f64 > time_budget {
-                break;
+        let time_budget = match self.core.budget {
+            Budget::Time => Some(AVG_TICK_TIME_SECS * self.core.skips as f64),
+            Budget::Paths(max_paths) => {
+                paths.truncate(max_paths.max(1));
+                None
             }
-            #[cfg(feature = "debug")]
-            {
-                self.paths_seen += 1;
-            }
-            let mut node = Rc::clone(&self.root);
-            let mut depth = 0;
-            for _ in 0..SIMULATION_DEPTH {
-                let commands: Vec<_> = (0..self.skips)
-                    .map(|_| {
-                        let command = path[depth.min(path.len() - 1)].clone();
-                        depth += 1;
-                        command
-                    })
-                    .collect();
-                let child = Rc::new(RefCell::new(Node {
-                    state: self.predict_states(&node.borrow().state, commands.as_ref()),
-                    commands: commands,
-                    parent: Rc::downgrade(&node),
-                    children: Default::default(),
-                }));
-                node.borrow_mut().children.push(Rc::clone(&child));
-                node = child;
+        };
+        let branches = simulate_paths(
+            self.core.simulator(),
+            self.core.tracker.state.clone(),
+            paths,
+            self.core.tick_start_time,
+            time_budget,
+        );
+        #[cfg(feature = "debug")]
+        {
+            self.paths_seen = branches.len() as i64;
+        }
+        for branch in branches {
+            let mut node = ROOT;
+            for (commands, state) in branch {
+                node = self.tree.add_child(node, commands, state);
             }
         }
     }
 
     fn generate_paths(&self) -> Vec<Vec<Command>> {
         let mut paths: Vec<Vec<Command>> = Vec::new();
-        for me in self.state.my_blobs.iter() {
-            let splits = if me.can_split(1) {
-                vec![false, true]
-            } else {
-                vec![false]
-            };
-            for split in splits {
+        let config = &self.core.tracker.config;
+        for me in self.core.tracker.state.my_blobs.iter() {
+            let mut actions = vec![(false, false)];
+            if me.can_split(1, config) {
+                actions.push((true, false));
+            }
+            if me.can_eject(config) && !self.core.tracker.state.enemies.is_empty() {
+                actions.push((false, true));
+            }
+            for (split, eject) in actions {
                 for angle in DISCOVERY_ANGLES.iter() {
                     let target = me.point() +
                         Point::from_polar(
-                            me.vision_radius(self.state.my_blobs.len()) * COMMAND_DISTANCE_FACTOR,
+                            me.vision_radius(self.core.tracker.state.my_blobs.len(), config) *
+                                COMMAND_DISTANCE_FACTOR,
                             me.angle() + angle,
                         );
                     paths.push(
                         (0..2)
                             .map(|i| {
-                                let mut command = Command::from_point(target);
+                                let mut command = Command::from_point(target, config);
                                 if split && i == 0 {
                                     command.set_split();
                                 }
+                                if eject && i == 0 {
+                                    command.set_eject();
+                                }
                                 command
                             })
                             .collect(),
                     );
                 }
             }
-        }
-        paths
-    }
-
-    fn update_skips(&mut self) {
-        let me = &self.state
-            .my_blobs
-            .iter()
-            .max_by(|a, b| a.m().partial_cmp(&b.m()).expect("incomparable mass"))
-            .expect("add_commands with no blobs");
-        let speed = (me.speed() + me.max_speed()) / 2.0;
-        self.skips = ((me.r() / speed).round() as i64).max(MIN_SKIPS);
-    }
-
-    fn next_root(&self) -> SharedNode {
-        let mut node = Rc::clone(&self.target);
-        loop {
-            let mut next = None;
-            if let Some(parent) = node.borrow().parent.upgrade() {
-                if parent.borrow().parent.upgrade().is_some() {
-                    next = Some(parent);
+            if me.can_eject(config) {
+                for virus in self.core.tracker.state.viruses.iter().filter(|virus| {
+                    me.can_see(*virus, self.core.tracker.state.my_blobs.len(), config)
+                })
+                {
+                    paths.push(
+                        (0..2)
+                            .map(|i| {
+                                let mut command = Command::from_point(virus.point(), config);
+                                if i == 0 {
+                                    command.set_eject();
+                                }
+                                command
+                            })
+                            .collect(),
+                    );
                 }
             }
-            match next {
-                Some(next) => node = next,
-                None => break,
-            }
         }
-        node
-    }
-
-    fn predict_states(&self, state: &State, commands: &[Command]) -> State {
-        let mut mechanic = Mechanic::new(state);
-        for command in commands.iter() {
-            mechanic.tick(command, &self.food, &self.ejections, &self.viruses);
+        if let Some(me) = biggest_blob(&self.core.tracker.state) {
+            for target in self.core.tracker.food_map.targets(
+                self.core.tracker.state.tick,
+                me.point(),
+                GOAL_TARGETS,
+            ) {
+                paths.push(
+                    (0..2)
+                        .map(|_| Command::from_point(target, config))
+                        .collect(),
+                );
+            }
         }
-        mechanic.state
+        paths
     }
 
-    fn update_enemies(&mut self, enemies: Vec<Player>) {
-        let tick = self.state.tick;
-        for mut enemy in enemies {
-            if let Some(ghost) = self.ghost_enemies
-                .get(&enemy.id())
-                .into_iter()
-                .filter(|ghost| ghost.last_seen == tick - 1)
-                .next()
-            {
-                let v = enemy.point() - ghost.player.point();
-                enemy.set_v(v);
-                enemy.update_is_fast();
-                enemy.set_ttf((ghost.player.ttf() - 1).max(0));
-            } else {
-                enemy.set_ttf((config().ticks_til_fusion as f64 * GHOST_TTF_FACTOR) as i64);
-            }
-            self.ghost_enemies.insert(
-                enemy.id(),
-                Ghost {
-                    player: enemy,
-                    last_seen: tick,
-                },
-            );
-        }
-        let ref my_blobs = self.state.my_blobs;
-        self.ghost_enemies.retain(|_, ghost| {
-            ghost.last_seen >= tick - GHOST_TICKS &&
-                (ghost.last_seen == tick ||
-                     !my_blobs.iter().any(|me| {
-                        me.can_see_safe(&ghost.player, my_blobs.len(), GHOST_VISIBILITY_FACTOR)
-                    }))
-        });
-        self.state.enemies = self.ghost_enemies
-            .values()
-            .map(|enemy| enemy.player.clone())
-            .collect();
+    fn next_root(&self) -> NodeId {
+        self.tree
+            .ancestors(self.target)
+            .take_while(|&node| node != ROOT)
+            .last()
+            .unwrap_or(ROOT)
     }
 
     #[cfg(feature = "debug")]
@@ -352,9 +249,9 @@ impl MyStrategy {
             Box::new(parents.iter().cycle().zip(children.iter()))
         }
 
-        fn go(node: &SharedNode, tree_size: &mut i64, command: &mut Command, num_blobs: usize) {
-            let node = node.borrow();
-            *tree_size = *tree_size + 1;
+        let tree_size = self.tree.len();
+        let num_blobs = self.core.tracker.state.my_blobs.len();
+        for node in self.tree.ids().map(|id| self.tree.node(id)) {
             let debug_skips = false;
             if debug_skips {
                 for me in node.state.my_blobs.iter() {
@@ -366,7 +263,7 @@ impl MyStrategy {
                     });
                 }
             }
-            for child in node.children.iter() {
+            for &child in node.children.iter() {
                 let debug_simulation_depth = true;
                 if debug_simulation_depth {
                     let color = if node.state.my_blobs.len() > num_blobs {
@@ -374,7 +271,7 @@ impl MyStrategy {
                     } else {
                         String::from("lightGray")
                     };
-                    let child = child.borrow();
+                    let child = self.tree.node(child);
                     for (n, c) in zip(&node.state.my_blobs, &child.state.my_blobs) {
                         command.add_debug_line(DebugLine {
                             a: n.point(),
@@ -384,19 +281,10 @@ impl MyStrategy {
                         });
                     }
                 }
-                go(child, tree_size, command, num_blobs);
             }
         }
 
-        let mut tree_size = 0;
-        go(
-            &self.root,
-            &mut tree_size,
-            command,
-            self.state.my_blobs.len(),
-        );
-
-        for enemy in self.next_root.borrow().state.enemies.iter() {
+        for enemy in self.tree.node(self.next_root).state.enemies.iter() {
             command.add_debug_circle(DebugCircle {
                 center: enemy.point(),
                 radius: enemy.r(),
@@ -404,7 +292,7 @@ impl MyStrategy {
                 opacity: 0.1,
             });
         }
-        for me in self.next_root.borrow().state.my_blobs.iter() {
+        for me in self.tree.node(self.next_root).state.my_blobs.iter() {
             command.add_debug_circle(DebugCircle {
                 center: me.point(),
                 radius: me.r(),
@@ -421,7 +309,7 @@ impl MyStrategy {
             opacity: 1.0,
         });
 
-        for me in self.target.borrow().state.my_blobs.iter() {
+        for me in self.tree.node(self.target).state.my_blobs.iter() {
             command.add_debug_circle(DebugCircle {
                 center: me.point(),
                 radius: 2.0,
@@ -429,25 +317,19 @@ impl MyStrategy {
                 opacity: 1.0,
             });
         }
-        let mut node = Rc::clone(&self.target);
-        loop {
-            let parent = match node.borrow().parent.upgrade() {
-                Some(parent) => parent,
+        for node in self.tree.ancestors(self.target).map(|id| self.tree.node(id)) {
+            let parent = match node.parent {
+                Some(parent) => self.tree.node(parent),
                 None => break,
             };
-            {
-                let parent = parent.borrow();
-                let node = node.borrow();
-                for (p, n) in zip(&parent.state.my_blobs, &node.state.my_blobs) {
-                    command.add_debug_line(DebugLine {
-                        a: n.point(),
-                        b: p.point(),
-                        color: String::from("black"),
-                        opacity: 1.0,
-                    });
-                }
+            for (p, n) in zip(&parent.state.my_blobs, &node.state.my_blobs) {
+                command.add_debug_line(DebugLine {
+                    a: n.point(),
+                    b: p.point(),
+                    color: String::from("black"),
+                    opacity: 1.0,
+                });
             }
-            node = parent;
         }
 
         use std::collections::HashSet;
@@ -464,11 +346,19 @@ impl MyStrategy {
             }
         }
 
-        let target_state = &self.target.borrow().state;
-        mark_eaten(&self.food, &target_state.eaten_food, command);
-        mark_eaten(&self.ejections, &target_state.eaten_ejections, command);
-        mark_eaten(&self.viruses, &target_state.eaten_viruses, command);
-        for enemy in self.state.enemies.iter() {
+        let target_state = &self.tree.node(self.target).state;
+        mark_eaten(&self.core.tracker.food, &target_state.eaten_food, command);
+        mark_eaten(
+            &self.core.tracker.state.ejections,
+            &target_state.eaten_ejections,
+            command,
+        );
+        mark_eaten(
+            &self.core.tracker.state.viruses,
+            &target_state.eaten_viruses,
+            command,
+        );
+        for enemy in self.core.tracker.state.enemies.iter() {
             if !target_state.enemies.iter().any(|player| {
                 player.id() == enemy.id()
             })
@@ -481,42 +371,66 @@ impl MyStrategy {
                 });
             }
         }
-        for me in self.state.my_blobs.iter() {
+        for me in self.core.tracker.state.my_blobs.iter() {
             command.add_debug_circle(DebugCircle {
-                center: me.point() + Point::from_polar(config().vis_shift, me.angle()),
-                radius: me.vision_radius(self.state.my_blobs.len()) * GHOST_VISIBILITY_FACTOR,
+                center: me.point() + Point::from_polar(self.core.tracker.config.vis_shift, me.angle()),
+                radius: me.vision_radius(self.core.tracker.state.my_blobs.len(), &self.core.tracker.config) *
+                    GHOST_VISIBILITY_FACTOR,
                 color: String::from("blue"),
                 opacity: 0.05,
             });
         }
-        for ghost in self.ghost_enemies.values() {
+        if let Some(goal) = self.core.tracker.goal {
+            command.add_debug_circle(DebugCircle {
+                center: goal,
+                radius: 8.0,
+                color: String::from("orange"),
+                opacity: 0.8,
+            });
+        }
+        for ghost in self.core.tracker.ghost_enemies.values() {
             command.add_debug_circle(DebugCircle {
                 center: ghost.player.point(),
                 radius: ghost.player.r(),
                 color: String::from("blue"),
                 opacity: 0.5,
             });
+            if ghost.uncertainty > 0.0 {
+                command.add_debug_circle(DebugCircle {
+                    center: ghost.player.point(),
+                    radius: ghost.player.r() + ghost.uncertainty,
+                    color: String::from("blue"),
+                    opacity: 0.1,
+                });
+            }
         }
 
-        command.add_debug_message(format!("skips:\t{}", self.skips));
+        command.add_debug_message(format!("skips:\t{}", self.core.skips));
         command.add_debug_message(format!("queue:\t{}", self.commands.len()));
         command.add_debug_message(format!("paths:\t{} / {}", self.paths_seen, self.num_paths));
         command.add_debug_message(format!("tree:\t{}", tree_size));
-        command.add_debug_message(format!("enemies:\t{}", self.state.enemies.len()));
-        command.add_debug_message(format!("food:\t{}", self.food.len()));
-        command.add_debug_message(format!("viruses:\t{}", self.viruses.len()));
+        command.add_debug_message(format!("enemies:\t{}", self.core.tracker.state.enemies.len()));
+        for &(player_id, model) in self.core.tracker.enemy_models.iter() {
+            command.add_debug_message(format!("model {}:\t{}", player_id, model.name()));
+        }
+        command.add_debug_message(format!("food:\t{}", self.core.tracker.food.len()));
+        command.add_debug_message(format!("viruses:\t{}", self.core.tracker.state.viruses.len()));
         command.add_debug_message(format!(
             "goal:\t{:.4}",
-            AVG_TICK_TIME_SECS * self.skips as f64
+            AVG_TICK_TIME_SECS * self.core.skips as f64
         ));
         command.add_debug_message(format!(
             "spent:\t{:.4}",
-            duration_to_secs(self.tick_start_time.elapsed())
+            duration_to_secs(self.core.tick_start_time.elapsed())
         ));
-        if self.target.borrow().state.my_blobs.is_empty() {
+        let breakdown = self.core.scorer.breakdown(target_state, &self.core.tracker);
+        for (term, score) in TERMS.iter().zip(breakdown.iter()) {
+            command.add_debug_message(format!("{}:\t{:.2}", term.name(), score));
+        }
+        if self.tree.node(self.target).state.my_blobs.is_empty() {
             command.add_debug_message(format!("ABOUT TO DIE"));
         }
-        if self.target.borrow().state.my_blobs.len() > self.state.my_blobs.len() {
+        if self.tree.node(self.target).state.my_blobs.len() > self.core.tracker.state.my_blobs.len() {
             command.add_debug_message(format!("ABOUT TO SPLIT"));
         }
         if command.split() {
@@ -528,36 +442,90 @@ impl MyStrategy {
     }
 }
 
+impl Planner for MyStrategy {
+    fn core_mut(&mut self) -> &mut PlannerCore {
+        &mut self.core
+    }
+}
+
 impl Strategy for MyStrategy {
-    fn tick(
-        &mut self,
-        tick: Tick,
-        my_blobs: Vec<Player>,
-        food: Vec<Food>,
-        ejections: Vec<Ejection>,
-        viruses: Vec<Virus>,
-        enemies: Vec<Player>,
-    ) -> Command {
-        if my_blobs.is_empty() {
+    fn on_start(&mut self, config: &Config) {
+        self.core.tracker.set_config(config);
+    }
+
+    fn tick(&mut self, world: &World) -> Command {
+        if world.my_blobs.is_empty() {
             return Default::default();
         }
-        self.tick_impl(tick, my_blobs, food, ejections, viruses, enemies)
+        self.tick_impl(world)
     }
 }
 
-fn find_nodes(root: &SharedNode) -> Vec<SharedNode> {
-    fn go(node: &SharedNode, nodes: &mut Vec<SharedNode>) {
-        nodes.push(Rc::clone(node));
-        for child in node.borrow().children.iter() {
-            go(child, nodes);
-        }
-    }
+#[cfg(not(feature = "parallel"))]
+fn simulate_paths(
+    simulator: Simulator,
+    root: State,
+    paths: Vec<Path>,
+    start: Instant,
+    time_budget: Option<f64>,
+) -> Vec<Branch> {
+    simulate_until(&simulator, &root, paths.into_iter().enumerate(), start, time_budget)
+        .into_iter()
+        .map(|(_, branch)| branch)
+        .collect()
+}
 
-    let mut nodes = vec![];
-    go(root, &mut nodes);
-    nodes
+#[cfg(feature = "parallel")]
+fn simulate_paths(
+    simulator: Simulator,
+    root: State,
+    paths: Vec<Path>,
+    start: Instant,
+    time_budget: Option<f64>,
+) -> Vec<Branch> {
+    let simulator = Arc::new(simulator);
+    let root = Arc::new(root);
+    let mut chunks: Vec<Vec<(usize, Path)>> = (0..WORKER_THREADS).map(|_| vec![]).collect();
+    for (i, path) in paths.into_iter().enumerate() {
+        chunks[i % WORKER_THREADS].push((i, path));
+    }
+    let workers: Vec<_> = chunks
+        .into_iter()
+        .map(|chunk| {
+            let simulator = Arc::clone(&simulator);
+            let root = Arc::clone(&root);
+            thread::spawn(move || {
+                simulate_until(&simulator, &root, chunk, start, time_budget)
+            })
+        })
+        .collect();
+    let mut branches: Vec<_> = workers
+        .into_iter()
+        .flat_map(|worker| worker.join().expect("path worker panicked"))
+        .collect();
+    branches.sort_by_key(|&(i, _)| i);
+    branches.into_iter().map(|(_, branch)| branch).collect()
 }
 
-fn duration_to_secs(duration: Duration) -> f64 {
-    duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 1e-9
+fn simulate_until<I>(
+    simulator: &Simulator,
+    root: &State,
+    paths: I,
+    start: Instant,
+    time_budget: Option<f64>,
+) -> Vec<(usize, Branch)>
+where
+    I: IntoIterator<Item = (usize, Path)>,
+{
+    let mut branches = vec![];
+    for (i, (path_index, path)) in paths.into_iter().enumerate() {
+        if let Some(time_budget) = time_budget {
+            let elapsed = duration_to_secs(start.elapsed());
+            if i != 0 && elapsed * (i + 1) as f64 / i as f64 > time_budget {
+                break;
+            }
+        }
+        branches.push((path_index, simulator.simulate_path(root, &path)));
+    }
+    branches
 }
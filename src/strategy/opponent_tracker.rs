@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+
+use strategy::*;
+use strategy::enemy_model::{EnemyContext, EnemyModel, ENEMY_MODELS};
+use strategy::mechanic::apply_direct;
+
+const MIN_OBSERVATIONS: i64 = 10;
+const ERROR_DECAY: f64 = 0.95;
+const SPLIT_ERROR: f64 = 10.0;
+
+#[derive(Debug, Default)]
+pub struct OpponentTracker {
+    opponents: BTreeMap<PlayerId, Opponent>,
+}
+
+#[derive(Debug)]
+struct Opponent {
+    observations: i64,
+    errors: Vec<f64>,
+}
+
+impl OpponentTracker {
+    pub fn new() -> OpponentTracker {
+        Default::default()
+    }
+
+    pub fn observe(
+        &mut self,
+        enemy: &Player,
+        observed_v: Point,
+        observed_split: bool,
+        context: &EnemyContext,
+    ) {
+        if enemy.is_fast() && !observed_split {
+            return;
+        }
+        let opponent = self.opponents.entry(enemy.player_id()).or_insert_with(|| {
+            Opponent {
+                observations: 0,
+                errors: vec![0.0; ENEMY_MODELS.len()],
+            }
+        });
+        opponent.observations += 1;
+        for (error, model) in opponent.errors.iter_mut().zip(ENEMY_MODELS.iter()) {
+            let command = model.command(enemy, context);
+            let predicted_split = command.as_ref().map_or(false, |command| command.split());
+            *error *= ERROR_DECAY;
+            if !enemy.is_fast() {
+                let mut predicted = enemy.clone();
+                if let Some(command) = command {
+                    apply_direct(&mut predicted, &command, context.config);
+                }
+                *error += predicted.v().qdist(observed_v);
+            }
+            // Splits are rare, so a correct prediction earns as much as a wrong one costs.
+            *error += match (predicted_split, observed_split) {
+                (true, true) => -SPLIT_ERROR,
+                (false, false) => 0.0,
+                _ => SPLIT_ERROR,
+            };
+        }
+    }
+
+    pub fn model(&self, player_id: PlayerId) -> Option<&'static dyn EnemyModel> {
+        let opponent = self.opponents.get(&player_id)?;
+        if opponent.observations < MIN_OBSERVATIONS {
+            return None;
+        }
+        opponent
+            .errors
+            .iter()
+            .zip(ENEMY_MODELS.iter())
+            .min_by(|&(a, _), &(b, _)| a.partial_cmp(b).expect("incomparable errors"))
+            .map(|(_, &model)| model)
+    }
+
+    pub fn models(&self) -> Vec<(PlayerId, &'static dyn EnemyModel)> {
+        self.opponents
+            .keys()
+            .filter_map(|&player_id| {
+                self.model(player_id).map(|model| (player_id, model))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::Config;
+    use strategy::enemy_model::{GREEDY_CHASE, SPLIT_ATTACK};
+    use strategy::grid::Grid;
+
+    fn track(model: &dyn EnemyModel, observations: i64, enemy_m: Mass) -> OpponentTracker {
+        let config = Config::default();
+        let players = vec![
+            Player::new(2, 0, Point::new(500.0, 500.0), enemy_m, &config),
+            Player::new(1, 0, Point::new(600.0, 500.0), 40.0, &config),
+        ];
+        let food_grid = Grid::for_config(&config);
+        let context = EnemyContext {
+            my_player_id: 1,
+            players: &players,
+            food: &[],
+            food_grid: &food_grid,
+            eaten_food: &Default::default(),
+            config: &config,
+        };
+        let enemy = &players[0];
+        let command = model.command(enemy, &context).expect("model sends no command");
+        let mut observed = enemy.clone();
+        apply_direct(&mut observed, &command, &config);
+        let mut tracker = OpponentTracker::new();
+        for _ in 0..observations {
+            tracker.observe(enemy, observed.v(), command.split(), &context);
+        }
+        tracker
+    }
+
+    #[test]
+    fn waits_for_enough_observations() {
+        let tracker = track(&GREEDY_CHASE, MIN_OBSERVATIONS - 1, 100.0);
+        assert!(tracker.model(2).is_none());
+        assert!(tracker.models().is_empty());
+    }
+
+    #[test]
+    fn classifies_greedy_chase() {
+        let tracker = track(&GREEDY_CHASE, MIN_OBSERVATIONS, 100.0);
+        assert_eq!(tracker.model(2).map(|model| model.name()), Some(GREEDY_CHASE.name()));
+        assert_eq!(tracker.models().len(), 1);
+    }
+
+    #[test]
+    fn classifies_observed_splits_as_split_attack() {
+        let tracker = track(&SPLIT_ATTACK, MIN_OBSERVATIONS, 300.0);
+        assert_eq!(tracker.model(2).map(|model| model.name()), Some(SPLIT_ATTACK.name()));
+    }
+}
@@ -0,0 +1,59 @@
+use std::env;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde_json;
+
+const SEARCH_PARAMS_VAR: &str = "SEARCH_PARAMS";
+const SEARCH_PARAMS_LOCATION_VAR: &str = "SEARCH_PARAMS_LOCATION";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(default)]
+pub struct SearchParams {
+    pub simulation_depth: i64,
+    pub min_skips: i64,
+    pub ghost_ticks: i64,
+    pub min_target_confidence: f64,
+}
+
+impl Default for SearchParams {
+    fn default() -> SearchParams {
+        SearchParams {
+            simulation_depth: 7,
+            min_skips: 5,
+            ghost_ticks: 50,
+            min_target_confidence: 0.5,
+        }
+    }
+}
+
+impl SearchParams {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<SearchParams> {
+        load_json(path)
+    }
+
+    pub fn from_env() -> io::Result<SearchParams> {
+        from_env(SEARCH_PARAMS_VAR, SEARCH_PARAMS_LOCATION_VAR)
+    }
+}
+
+pub fn load_json<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> io::Result<T> {
+    serde_json::from_reader(File::open(path)?).map_err(invalid_data)
+}
+
+pub fn from_env<T: DeserializeOwned + Default>(var: &str, location_var: &str) -> io::Result<T> {
+    if let Ok(value) = env::var(var) {
+        return serde_json::from_str(&value).map_err(invalid_data);
+    }
+    if let Ok(path) = env::var(location_var) {
+        return load_json(path);
+    }
+    Ok(Default::default())
+}
+
+fn invalid_data(error: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
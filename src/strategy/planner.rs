@@ -0,0 +1,76 @@
+use std::time::Instant;
+
+use strategy::*;
+use strategy::enemy_model::{EnemyModel, GREEDY_CHASE};
+use strategy::simulator::{skips, within_budget, Simulator};
+use strategy::world_tracker::WorldTracker;
+
+// What every planning strategy tracks and gets configured with.
+#[derive(Debug)]
+pub struct PlannerCore {
+    pub tracker: WorldTracker,
+    pub scorer: Scorer,
+    pub enemy_model: &'static dyn EnemyModel,
+    pub budget: Budget,
+    pub tick_start_time: Instant,
+    pub skips: i64,
+}
+
+impl PlannerCore {
+    pub fn new() -> PlannerCore {
+        PlannerCore {
+            tracker: WorldTracker::new(),
+            scorer: Default::default(),
+            enemy_model: &GREEDY_CHASE,
+            budget: Budget::Time,
+            tick_start_time: Instant::now(),
+            skips: Default::default(),
+        }
+    }
+
+    pub fn start_tick(&mut self, world: &World) {
+        self.tick_start_time = Instant::now();
+        self.tracker.update(world);
+    }
+
+    pub fn update_skips(&mut self) {
+        self.skips = skips(
+            &self.tracker.state,
+            &self.tracker.config,
+            self.tracker.params.min_skips,
+        );
+    }
+
+    pub fn within_budget(&self, done: usize) -> bool {
+        within_budget(self.budget, self.tick_start_time, self.skips, done)
+    }
+
+    pub fn simulator(&self) -> Simulator {
+        Simulator {
+            config: self.tracker.config.clone(),
+            food: self.tracker.food.clone(),
+            food_grid: self.tracker.food_grid.clone(),
+            enemy_model: self.enemy_model,
+            enemy_models: self.tracker.enemy_models.clone(),
+            enemy_targets: self.tracker.enemy_targets.clone(),
+            skips: self.skips,
+            depth: self.tracker.params.simulation_depth,
+        }
+    }
+}
+
+pub trait Planner {
+    fn core_mut(&mut self) -> &mut PlannerCore;
+
+    fn set_budget(&mut self, budget: Budget) {
+        self.core_mut().budget = budget;
+    }
+
+    fn set_scorer(&mut self, scorer: Scorer) {
+        self.core_mut().scorer = scorer;
+    }
+
+    fn set_search_params(&mut self, params: SearchParams) {
+        self.core_mut().tracker.params = params;
+    }
+}
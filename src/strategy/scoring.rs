@@ -0,0 +1,241 @@
+use std::io;
+use std::path::Path;
+
+use strategy::*;
+use strategy::mechanic::State;
+use strategy::params::{from_env, load_json};
+use strategy::world_tracker::WorldTracker;
+
+pub type Score = f64;
+
+const SCORE_WEIGHTS_VAR: &str = "SCORE_WEIGHTS";
+const SCORE_WEIGHTS_LOCATION_VAR: &str = "SCORE_WEIGHTS_LOCATION";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Term {
+    Mass,
+    Speed,
+    SmallBlob,
+    Danger,
+    Prey,
+    WallMargin,
+    FoodProximity,
+    FusionReadiness,
+    GlobalGoal,
+}
+
+// Keep GlobalGoal the last term, so the count follows the enum.
+pub const TERM_COUNT: usize = Term::GlobalGoal as usize + 1;
+
+pub const TERMS: [Term; TERM_COUNT] = [
+    Term::Mass,
+    Term::Speed,
+    Term::SmallBlob,
+    Term::Danger,
+    Term::Prey,
+    Term::WallMargin,
+    Term::FoodProximity,
+    Term::FusionReadiness,
+    Term::GlobalGoal,
+];
+
+pub type Breakdown = [Score; TERM_COUNT];
+
+impl Term {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Term::Mass => "mass",
+            Term::Speed => "speed",
+            Term::SmallBlob => "small blob",
+            Term::Danger => "danger",
+            Term::Prey => "prey",
+            Term::WallMargin => "wall margin",
+            Term::FoodProximity => "food proximity",
+            Term::FusionReadiness => "fusion readiness",
+            Term::GlobalGoal => "global goal",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[serde(default)]
+pub struct ScoreWeights {
+    pub mass: f64,
+    pub speed: f64,
+    pub small_blob: f64,
+    pub max_small_blob_mass: f64,
+    pub danger: f64,
+    pub ghost_uncertainty: f64,
+    pub prey: f64,
+    pub wall_margin: f64,
+    pub wall_margin_factor: f64,
+    pub food_proximity: f64,
+    pub fusion_readiness: f64,
+    pub global_goal: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> ScoreWeights {
+        ScoreWeights {
+            mass: 1.0,
+            speed: 0.01,
+            small_blob: -10.0,
+            max_small_blob_mass: 85.0,
+            danger: -300.0,
+            ghost_uncertainty: 1.0,
+            prey: 0.5,
+            wall_margin: -5.0,
+            wall_margin_factor: 7.0,
+            food_proximity: 0.0,
+            fusion_readiness: 0.0,
+            global_goal: 10.0,
+        }
+    }
+}
+
+impl ScoreWeights {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<ScoreWeights> {
+        load_json(path)
+    }
+
+    pub fn from_env() -> io::Result<ScoreWeights> {
+        from_env(SCORE_WEIGHTS_VAR, SCORE_WEIGHTS_LOCATION_VAR)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Scorer {
+    weights: ScoreWeights,
+}
+
+impl Scorer {
+    pub fn new(weights: ScoreWeights) -> Scorer {
+        Scorer { weights }
+    }
+
+    pub fn weights(&self) -> &ScoreWeights {
+        &self.weights
+    }
+
+    pub fn score(&self, state: &State, tracker: &WorldTracker) -> Score {
+        state
+            .my_blobs
+            .iter()
+            .map(|me| {
+                TERMS
+                    .iter()
+                    .map(|&term| self.term(term, me, state, tracker))
+                    .sum::<Score>()
+            })
+            .sum()
+    }
+
+    pub fn breakdown(&self, state: &State, tracker: &WorldTracker) -> Breakdown {
+        let mut breakdown = [0.0; TERM_COUNT];
+        for me in state.my_blobs.iter() {
+            for (score, &term) in breakdown.iter_mut().zip(TERMS.iter()) {
+                *score += self.term(term, me, state, tracker);
+            }
+        }
+        breakdown
+    }
+
+    fn term(&self, term: Term, me: &Player, state: &State, tracker: &WorldTracker) -> Score {
+        let weights = &self.weights;
+        let config = &tracker.config;
+        match term {
+            Term::Mass => weights.mass * me.m(),
+            Term::Speed => weights.speed * me.speed(),
+            Term::SmallBlob => {
+                if me.m() <= weights.max_small_blob_mass {
+                    weights.small_blob
+                } else {
+                    0.0
+                }
+            }
+            Term::Danger => {
+                let mut score = 0.0;
+                for enemy in state.enemies.iter().filter(|enemy| enemy.m() > me.m()) {
+                    let mut speed = enemy.max_speed(config);
+                    if enemy.m() > me.m() * 2.0 {
+                        speed = speed.max(config.split_start_speed);
+                    }
+                    let uncertainty = tracker.ghost_enemies.get(&enemy.id()).map_or(
+                        0.0,
+                        |ghost| ghost.uncertainty,
+                    );
+                    let dist = me.point().dist(enemy.point()) -
+                        weights.ghost_uncertainty * uncertainty;
+                    score += weights.danger / (dist / speed).max(1.0).min(3.0);
+                }
+                score
+            }
+            Term::Prey => {
+                let mut score = 0.0;
+                for enemy in state.enemies.iter().filter(|enemy| {
+                    enemy.m() <= me.m() && me.m() > enemy.m() * config.mass_eat_factor
+                })
+                {
+                    let dist = me.point().dist(enemy.point());
+                    score += weights.prey * enemy.m() / (dist / me.max_speed(config)).max(1.0);
+                }
+                score
+            }
+            Term::WallMargin => {
+                let mut score = 0.0;
+                let safety_margin = me.r() * weights.wall_margin_factor;
+                if me.x() < safety_margin || me.x() > config.game_width as f64 - safety_margin {
+                    score += weights.wall_margin;
+                }
+                if me.y() < safety_margin || me.y() > config.game_height as f64 - safety_margin {
+                    score += weights.wall_margin;
+                }
+                score
+            }
+            Term::FoodProximity => {
+                if weights.food_proximity == 0.0 {
+                    return 0.0;
+                }
+                tracker
+                    .food
+                    .iter()
+                    .filter(|food| !state.eaten_food.contains(&food.id()))
+                    .map(|food| me.point().dist(food.point()))
+                    .min_by(|a, b| a.partial_cmp(b).expect("incomparable distances"))
+                    .map_or(0.0, |dist| {
+                        weights.food_proximity / (dist / me.max_speed(config)).max(1.0)
+                    })
+            }
+            Term::FusionReadiness => {
+                if weights.fusion_readiness == 0.0 || state.my_blobs.len() == 1 {
+                    return 0.0;
+                }
+                let ttf = me.ttf() as f64 / config.ticks_til_fusion as f64;
+                weights.fusion_readiness * (1.0 - ttf.min(1.0))
+            }
+            Term::GlobalGoal => {
+                let goal = match tracker.goal {
+                    Some(goal) => goal,
+                    None => return 0.0,
+                };
+                let diagonal = (config.game_width as f64).hypot(config.game_height as f64);
+                let total_mass: f64 = state.my_blobs.iter().map(|blob| blob.m()).sum();
+                let closeness = 1.0 - me.point().dist(goal) / diagonal;
+                weights.global_goal * closeness * me.m() / total_mass
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_term_in_order() {
+        for (i, &term) in TERMS.iter().enumerate() {
+            assert_eq!(term as usize, i, "{}", term.name());
+        }
+    }
+}
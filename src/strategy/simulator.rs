@@ -0,0 +1,94 @@
+use std::time::{Duration, Instant};
+
+use config::Config;
+use strategy::*;
+use strategy::enemy_model::EnemyModel;
+use strategy::grid::Grid;
+use strategy::mechanic::{Mechanic, State};
+
+pub const AVG_TICK_TIME_SECS: f64 = 600.0 / 25000.0;
+
+pub type Path = Vec<Command>;
+pub type Branch = Vec<(Vec<Command>, State)>;
+
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    pub config: Config,
+    pub food: Vec<Food>,
+    pub food_grid: Grid,
+    pub enemy_model: &'static dyn EnemyModel,
+    pub enemy_models: Vec<(PlayerId, &'static dyn EnemyModel)>,
+    pub enemy_targets: Vec<(PlayerBlobId, Point)>,
+    pub skips: i64,
+    pub depth: i64,
+}
+
+impl Simulator {
+    pub fn simulate_path(&self, root: &State, path: &[Command]) -> Branch {
+        let mut branch: Branch = vec![];
+        let mut depth = 0;
+        for _ in 0..self.depth {
+            let commands: Vec<_> = (0..self.skips)
+                .map(|_| {
+                    let command = path[depth.min(path.len() - 1)].clone();
+                    depth += 1;
+                    command
+                })
+                .collect();
+            let state = self.predict_states(
+                branch.last().map_or(root, |(_, state)| state),
+                &commands,
+            );
+            branch.push((commands, state));
+        }
+        branch
+    }
+
+    pub fn predict_states(&self, state: &State, commands: &[Command]) -> State {
+        self.predict_states_with(state, commands, &self.enemy_models)
+    }
+
+    pub fn predict_states_with(
+        &self,
+        state: &State,
+        commands: &[Command],
+        enemy_models: &[(PlayerId, &'static dyn EnemyModel)],
+    ) -> State {
+        let mut mechanic = Mechanic::new(state, &self.config);
+        mechanic.set_food_grid(&self.food_grid);
+        mechanic.set_default_enemy_model(self.enemy_model);
+        mechanic.set_enemy_models(enemy_models);
+        mechanic.set_enemy_targets(&self.enemy_targets);
+        for command in commands.iter() {
+            mechanic.tick(command, &self.food);
+        }
+        mechanic.state
+    }
+}
+
+pub fn skips(state: &State, config: &Config, min_skips: i64) -> i64 {
+    let me = biggest_blob(state).expect("skips with no blobs");
+    let speed = (me.speed() + me.max_speed(config)) / 2.0;
+    ((me.r() / speed).round() as i64).max(min_skips)
+}
+
+pub fn within_budget(budget: Budget, start: Instant, skips: i64, done: usize) -> bool {
+    match budget {
+        Budget::Time => {
+            let time_budget = AVG_TICK_TIME_SECS * skips as f64;
+            let elapsed = duration_to_secs(start.elapsed());
+            done == 0 || elapsed * (done + 1) as f64 / done as f64 <= time_budget
+        }
+        Budget::Paths(max_paths) => done == 0 || done < max_paths,
+    }
+}
+
+pub fn biggest_blob(state: &State) -> Option<&Player> {
+    state.my_blobs.iter().max_by(|a, b| {
+        a.m().partial_cmp(&b.m()).expect("incomparable mass")
+    })
+}
+
+pub fn duration_to_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 1e-9
+}
@@ -1,16 +1,51 @@
 use models::*;
 use command::Command;
+use config::Config;
+use protocol::{GameSummary, Notice};
 
 pub type Tick = i64;
 
+#[derive(Debug)]
+pub struct World<'a> {
+    pub tick: Tick,
+    pub my_blobs: Vec<Player>,
+    pub food: Vec<Food>,
+    pub ejections: Vec<Ejection>,
+    pub viruses: Vec<Virus>,
+    pub enemies: Vec<Player>,
+    pub config: &'a Config,
+}
+
 pub trait Strategy {
-    fn tick(
-        &mut self,
-        tick: Tick,
-        my_blobs: Vec<Player>,
-        food: Vec<Food>,
-        ejections: Vec<Ejection>,
-        viruses: Vec<Virus>,
-        enemies: Vec<Player>,
-    ) -> Command;
+    fn on_start(&mut self, _config: &Config) {}
+
+    fn tick(&mut self, world: &World) -> Command;
+
+    fn on_game_over(&mut self, _summary: &GameSummary) {}
+
+    fn on_notice(&mut self, _notice: &Notice) {}
+
+    fn on_game_end(&mut self) {}
+}
+
+impl<S: Strategy + ?Sized> Strategy for Box<S> {
+    fn on_start(&mut self, config: &Config) {
+        (**self).on_start(config);
+    }
+
+    fn tick(&mut self, world: &World) -> Command {
+        (**self).tick(world)
+    }
+
+    fn on_game_over(&mut self, summary: &GameSummary) {
+        (**self).on_game_over(summary);
+    }
+
+    fn on_notice(&mut self, notice: &Notice) {
+        (**self).on_notice(notice);
+    }
+
+    fn on_game_end(&mut self) {
+        (**self).on_game_end();
+    }
 }
@@ -1,5 +1,7 @@
 use time::precise_time_s;
 
+use config::Config;
+use protocol::{GameSummary, Notice};
 use strategy::*;
 
 type Seconds = f64;
@@ -21,28 +23,29 @@ impl<S: Strategy> TimingWrapper<S> {
 }
 
 impl<S: Strategy> Strategy for TimingWrapper<S> {
-    fn tick(
-        &mut self,
-        tick: i64,
-        my_blobs: Vec<Player>,
-        food: Vec<Food>,
-        ejections: Vec<Ejection>,
-        viruses: Vec<Virus>,
-        enemies: Vec<Player>,
-    ) -> Command {
+    fn on_start(&mut self, config: &Config) {
+        self.strategy.on_start(config);
+    }
+
+    fn tick(&mut self, world: &World) -> Command {
         let start = precise_time_s();
-        let mut command = self.strategy.tick(
-            tick,
-            my_blobs,
-            food,
-            ejections,
-            viruses,
-            enemies,
-        );
+        let mut command = self.strategy.tick(world);
         self.total += precise_time_s() - start;
-        let expected = AVG_TICK_TIME_SECS * (tick + 1) as Seconds;
+        let expected = AVG_TICK_TIME_SECS * (world.tick + 1) as Seconds;
         command.add_debug_message(format!("total:\t{:.2}", self.total));
         command.add_debug_message(format!("budget:\t{:.2}", expected - self.total));
         command
     }
+
+    fn on_game_over(&mut self, summary: &GameSummary) {
+        self.strategy.on_game_over(summary);
+    }
+
+    fn on_notice(&mut self, notice: &Notice) {
+        self.strategy.on_notice(notice);
+    }
+
+    fn on_game_end(&mut self) {
+        self.strategy.on_game_end();
+    }
 }
@@ -0,0 +1,193 @@
+use std::mem;
+use std::ops::Range;
+
+use strategy::*;
+use strategy::mechanic::State;
+
+pub type NodeId = usize;
+
+pub const ROOT: NodeId = 0;
+
+#[derive(Debug, Default)]
+pub struct Tree {
+    nodes: Vec<Node>,
+    spare: Vec<Node>,
+}
+
+#[derive(Debug, Default)]
+pub struct Node {
+    pub state: State,
+    pub commands: Vec<Command>,
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+}
+
+impl Tree {
+    pub fn new() -> Tree {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn ids(&self) -> Range<NodeId> {
+        0..self.nodes.len()
+    }
+
+    pub fn reset(&mut self, state: State) {
+        self.nodes.clear();
+        self.nodes.push(Node {
+            state,
+            ..Default::default()
+        });
+    }
+
+    pub fn add_child(&mut self, parent: NodeId, commands: Vec<Command>, state: State) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            state,
+            commands,
+            parent: Some(parent),
+            children: Default::default(),
+        });
+        self.nodes[parent].children.push(id);
+        id
+    }
+
+    // Returns the old ids of the kept nodes, indexed by their new ids.
+    pub fn reroot(&mut self, new_root: NodeId, state: State) -> Vec<NodeId> {
+        mem::swap(&mut self.nodes, &mut self.spare);
+        self.nodes.clear();
+        let mut root = mem::replace(&mut self.spare[new_root], Default::default());
+        let mut queue = vec![new_root];
+        queue.append(&mut root.children);
+        self.nodes.push(Node {
+            state,
+            ..Default::default()
+        });
+        let mut parents = vec![ROOT; queue.len()];
+        let mut i = 1;
+        while i < queue.len() {
+            let mut node = mem::replace(&mut self.spare[queue[i]], Default::default());
+            let children = mem::replace(&mut node.children, vec![]);
+            node.parent = Some(parents[i]);
+            let id = self.nodes.len();
+            self.nodes[parents[i]].children.push(id);
+            self.nodes.push(node);
+            parents.extend(children.iter().map(|_| id));
+            queue.extend(children);
+            i += 1;
+        }
+        self.spare.clear();
+        queue
+    }
+
+    pub fn ancestors<'a>(&'a self, id: NodeId) -> Ancestors<'a> {
+        Ancestors {
+            tree: self,
+            next: Some(id),
+        }
+    }
+}
+
+pub struct Ancestors<'a> {
+    tree: &'a Tree,
+    next: Option<NodeId>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self.tree.node(id).parent;
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(tick: Tick) -> State {
+        State {
+            tick,
+            ..Default::default()
+        }
+    }
+
+    fn ticks(tree: &Tree, ids: &[NodeId]) -> Vec<Tick> {
+        ids.iter().map(|&id| tree.node(id).state.tick).collect()
+    }
+
+    // 0 -> (1 -> (3, 4 -> 6), 2 -> 5), with each node's tick equal to its label.
+    fn sample_tree() -> (Tree, NodeId) {
+        let mut tree = Tree::new();
+        tree.reset(state(0));
+        let a = tree.add_child(ROOT, vec![], state(1));
+        let b = tree.add_child(ROOT, vec![], state(2));
+        tree.add_child(a, vec![], state(3));
+        let d = tree.add_child(a, vec![], state(4));
+        tree.add_child(b, vec![], state(5));
+        tree.add_child(d, vec![], state(6));
+        (tree, a)
+    }
+
+    #[test]
+    fn reroot_keeps_the_subtree_of_the_new_root() {
+        let (mut tree, a) = sample_tree();
+        let old_ids = tree.reroot(a, state(10));
+        assert_eq!(old_ids, vec![a, 3, 4, 6]);
+        assert_eq!(tree.len(), 4);
+        let ids: Vec<_> = tree.ids().collect();
+        assert_eq!(ticks(&tree, &ids), vec![10, 3, 4, 6]);
+        assert_eq!(tree.node(ROOT).parent, None);
+        assert_eq!(tree.node(ROOT).children, vec![1, 2]);
+        assert_eq!(tree.node(1).parent, Some(ROOT));
+        assert!(tree.node(1).children.is_empty());
+        assert_eq!(tree.node(2).parent, Some(ROOT));
+        assert_eq!(tree.node(2).children, vec![3]);
+        assert_eq!(tree.node(3).parent, Some(2));
+        assert_eq!(tree.ancestors(3).collect::<Vec<_>>(), vec![3, 2, ROOT]);
+    }
+
+    #[test]
+    fn reroot_reuses_node_storage() {
+        let (mut tree, a) = sample_tree();
+        let buffer = tree.nodes.as_ptr();
+        tree.reroot(a, state(10));
+        tree.reroot(2, state(20));
+        assert_eq!(tree.len(), 2);
+        assert_eq!(ticks(&tree, &[ROOT, 1]), vec![20, 6]);
+        assert_eq!(tree.node(ROOT).children, vec![1]);
+        assert_eq!(tree.node(1).parent, Some(ROOT));
+        assert_eq!(tree.nodes.as_ptr(), buffer);
+        assert!(tree.spare.is_empty());
+    }
+
+    #[test]
+    fn reset_drops_all_nodes_but_the_root() {
+        let (mut tree, _) = sample_tree();
+        tree.reset(state(20));
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree.node(ROOT).state.tick, 20);
+        assert_eq!(tree.node(ROOT).parent, None);
+        assert!(tree.node(ROOT).children.is_empty());
+        let child = tree.add_child(ROOT, vec![], state(21));
+        assert_eq!(child, 1);
+        assert_eq!(tree.ancestors(child).collect::<Vec<_>>(), vec![1, ROOT]);
+    }
+}
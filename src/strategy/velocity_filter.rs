@@ -0,0 +1,151 @@
+use config::Config;
+use strategy::*;
+
+const POSITION_NOISE: f64 = 0.1;
+const TARGET_DISTANCE: f64 = 300.0;
+
+type Covariance = [[f64; 3]; 3];
+
+// Tracks a blob's position, velocity and acceleration with a constant-acceleration Kalman filter.
+// Both axes share the same covariance, since they get the same noise.
+#[derive(Debug, Clone)]
+pub struct VelocityFilter {
+    tick: Tick,
+    x: [f64; 3],
+    y: [f64; 3],
+    covariance: Covariance,
+}
+
+impl VelocityFilter {
+    pub fn new(tick: Tick, player: &Player, velocity_variance: f64) -> VelocityFilter {
+        let point = player.point();
+        let v = player.v();
+        let mut covariance = [[0.0; 3]; 3];
+        covariance[0][0] = POSITION_NOISE.powi(2);
+        covariance[1][1] = velocity_variance;
+        covariance[2][2] = velocity_variance;
+        VelocityFilter {
+            tick,
+            x: [point.x, v.x, 0.0],
+            y: [point.y, v.y, 0.0],
+            covariance,
+        }
+    }
+
+    pub fn update(&mut self, tick: Tick, player: &Player, config: &Config) {
+        let acceleration = max_acceleration(player, config);
+        for _ in self.tick..tick {
+            self.predict(acceleration.powi(2));
+        }
+        self.tick = tick;
+
+        let p = &mut self.covariance;
+        let s = p[0][0] + POSITION_NOISE.powi(2);
+        let gain = [p[0][0] / s, p[1][0] / s, p[2][0] / s];
+        let point = player.point();
+        let (dx, dy) = (point.x - self.x[0], point.y - self.y[0]);
+        for ((x, y), k) in self.x.iter_mut().zip(self.y.iter_mut()).zip(gain.iter()) {
+            *x += k * dx;
+            *y += k * dy;
+        }
+        let first_row = p[0];
+        for (row, k) in p.iter_mut().zip(gain.iter()) {
+            for (value, first) in row.iter_mut().zip(first_row.iter()) {
+                *value -= k * first;
+            }
+        }
+    }
+
+    pub fn point(&self) -> Point {
+        Point::new(self.x[0], self.y[0])
+    }
+
+    pub fn velocity(&self) -> Point {
+        Point::new(self.x[1], self.y[1])
+    }
+
+    pub fn acceleration(&self) -> Point {
+        Point::new(self.x[2], self.y[2])
+    }
+
+    pub fn velocity_variance(&self) -> f64 {
+        self.covariance[1][1]
+    }
+
+    // Inverts apply_direct to find where the blob is heading. Returns the target and a confidence
+    // between 0 and 1 that grows as the implied target speed gets close to the blob's max speed.
+    pub fn target(&self, player: &Player, config: &Config) -> Option<(Point, f64)> {
+        if player.is_fast() {
+            return None;
+        }
+        let max_speed = player.max_speed(config);
+        let inertia = (config.inertion_factor / player.m()).min(1.0);
+        let target_v = self.velocity() + self.acceleration() * (1.0 / inertia);
+        if target_v.length() == 0.0 {
+            return None;
+        }
+        let speed_fit = 1.0 - ((target_v.length() - max_speed).abs() / max_speed).min(1.0);
+        let certainty = max_speed.powi(2) / (max_speed.powi(2) + self.velocity_variance());
+        let target = player.point() + target_v.with_length(TARGET_DISTANCE);
+        Some((target, speed_fit * certainty))
+    }
+
+    fn predict(&mut self, q: f64) {
+        for state in [&mut self.x, &mut self.y].iter_mut() {
+            state[0] += state[1] + state[2] / 2.0;
+            state[1] += state[2];
+        }
+        let f = [[1.0, 1.0, 0.5], [0.0, 1.0, 1.0], [0.0, 0.0, 1.0]];
+        let noise = [
+            [1.0 / 20.0, 1.0 / 8.0, 1.0 / 6.0],
+            [1.0 / 8.0, 1.0 / 3.0, 1.0 / 2.0],
+            [1.0 / 6.0, 1.0 / 2.0, 1.0],
+        ];
+        let p = &self.covariance;
+        let mut next = [[0.0; 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                let mut sum = 0.0;
+                for k in 0..3 {
+                    for l in 0..3 {
+                        sum += f[i][k] * p[k][l] * f[j][l];
+                    }
+                }
+                next[i][j] = sum + q * noise[i][j];
+            }
+        }
+        self.covariance = next;
+    }
+}
+
+fn max_acceleration(player: &Player, config: &Config) -> f64 {
+    2.0 * player.max_speed(config) * (config.inertion_factor / player.m()).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(point: Point, m: Mass, config: &Config) -> Player {
+        Player::new(2, 0, point, m, config)
+    }
+
+    #[test]
+    fn converges_to_constant_velocity() {
+        let config = Config::default();
+        let m = 100.0;
+        let v = Point::new(1.5, -0.5);
+        let start = Point::new(300.0, 300.0);
+        let velocity_variance = player(start, m, &config).max_speed(&config).powi(2);
+        let mut filter = VelocityFilter::new(0, &player(start, m, &config), velocity_variance);
+        let mut previous_variance = filter.velocity_variance();
+        for tick in 1..40 {
+            filter.update(tick, &player(start + v * tick as f64, m, &config), &config);
+            assert!(filter.velocity_variance() <= previous_variance + 1e-9);
+            previous_variance = filter.velocity_variance();
+        }
+        assert!(filter.velocity().dist(v) < 0.05, "{:?}", filter.velocity());
+        assert!(filter.acceleration().length() < 0.05);
+        assert!(filter.velocity_variance() < velocity_variance / 10.0);
+    }
+}
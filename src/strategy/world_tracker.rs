@@ -0,0 +1,444 @@
+use std::collections::BTreeMap;
+use std::mem;
+
+use config::Config;
+use strategy::*;
+use strategy::enemy_model::{EnemyContext, EnemyModel, GREEDY_CHASE};
+use strategy::food_map::FoodMap;
+use strategy::fragment_tracker::{Event, FragmentTracker};
+use strategy::grid::Grid;
+use strategy::mechanic::{self, observed_impulse, State};
+use strategy::opponent_tracker::OpponentTracker;
+use strategy::simulator::biggest_blob;
+use strategy::velocity_filter::VelocityFilter;
+
+pub const GHOST_VISIBILITY_FACTOR: f64 = 0.80;
+pub const GHOST_TTF_FACTOR: f64 = 0.5;
+
+#[derive(Debug)]
+pub struct WorldTracker {
+    pub config: Config,
+    pub params: SearchParams,
+    pub state: State,
+    pub food: Vec<Food>,
+    pub food_grid: Grid,
+    pub food_map: FoodMap,
+    pub goal: Option<Point>,
+    pub ghost_enemies: BTreeMap<PlayerBlobId, Ghost>,
+    pub enemy_models: Vec<(PlayerId, &'static dyn EnemyModel)>,
+    pub fragment_tracker: FragmentTracker,
+    pub velocity_filters: BTreeMap<PlayerBlobId, VelocityFilter>,
+    pub enemy_targets: Vec<(PlayerBlobId, Point)>,
+    opponent_tracker: OpponentTracker,
+}
+
+#[derive(Debug)]
+pub struct Ghost {
+    pub player: Player,
+    pub last_seen: Tick,
+    pub uncertainty: f64,
+}
+
+impl WorldTracker {
+    pub fn new() -> WorldTracker {
+        WorldTracker {
+            config: Default::default(),
+            params: Default::default(),
+            state: Default::default(),
+            food: Default::default(),
+            food_grid: Grid::for_config(&Default::default()),
+            food_map: Default::default(),
+            goal: None,
+            ghost_enemies: Default::default(),
+            enemy_models: Default::default(),
+            fragment_tracker: FragmentTracker::new(),
+            velocity_filters: Default::default(),
+            enemy_targets: Default::default(),
+            opponent_tracker: OpponentTracker::new(),
+        }
+    }
+
+    pub fn set_config(&mut self, config: &Config) {
+        self.config = config.clone();
+        self.food_map = FoodMap::new(config);
+        self.food_grid = Grid::for_config(config);
+    }
+
+    pub fn update(&mut self, world: &World) {
+        let (enemies, gone) = self.link_fragments(world);
+        self.classify_enemies(world.tick, &enemies);
+        for id in gone {
+            self.ghost_enemies.remove(&id);
+        }
+        self.food = world.food.clone();
+        self.food_grid.rebuild(&self.food);
+        self.state.tick = world.tick;
+        self.state.my_blobs = world.my_blobs.clone();
+        self.update_ejections(world.ejections.clone());
+        self.update_viruses(world.viruses.clone());
+        self.state.eaten_food = Default::default();
+        self.state.eaten_ejections = Default::default();
+        self.state.eaten_viruses = Default::default();
+        self.update_enemies(enemies);
+        self.food_map.update(
+            world.tick,
+            &self.food,
+            &self.state.my_blobs,
+            &self.config,
+        );
+        self.goal = if self.food.is_empty() {
+            biggest_blob(&self.state).and_then(|me| {
+                self.food_map.targets(world.tick, me.point(), 1).pop()
+            })
+        } else {
+            None
+        };
+    }
+
+    fn update_ejections(&mut self, ejections: Vec<Ejection>) {
+        let last_seen = mem::replace(&mut self.state.ejections, ejections);
+        for ejection in self.state.ejections.iter_mut() {
+            if let Some(last) = last_seen.iter().find(|last| last.id() == ejection.id()) {
+                let v = observed_impulse(ejection.point() - last.point(), &self.config);
+                ejection.set_v(v);
+            }
+        }
+    }
+
+    fn update_viruses(&mut self, viruses: Vec<Virus>) {
+        let last_seen = mem::replace(&mut self.state.viruses, viruses);
+        for virus in self.state.viruses.iter_mut() {
+            if let Some(last) = last_seen.iter().find(|last| last.id() == virus.id()) {
+                let v = observed_impulse(virus.point() - last.point(), &self.config);
+                virus.set_v(v);
+            }
+        }
+    }
+
+    fn classify_enemies(&mut self, tick: Tick, enemies: &[Player]) {
+        let players: Vec<_> = self.state
+            .my_blobs
+            .iter()
+            .chain(self.state.enemies.iter())
+            .cloned()
+            .collect();
+        let context = EnemyContext {
+            my_player_id: self.state.my_blobs.first().map_or(
+                <PlayerId>::max_value(),
+                |me| me.player_id(),
+            ),
+            players: &players,
+            food: &self.food,
+            food_grid: &self.food_grid,
+            eaten_food: &Default::default(),
+            config: &self.config,
+        };
+        let fragment_tracker = &self.fragment_tracker;
+        let split_from = |parent: PlayerBlobId, enemy: &Player| {
+            fragment_tracker.lineage(enemy.id()).map_or(false, |lineage| {
+                lineage.tick == tick && lineage.parent == parent && lineage.event == Event::Split
+            })
+        };
+        for ghost in self.ghost_enemies.values().filter(
+            |ghost| ghost.last_seen == tick - 1,
+        )
+        {
+            let id = ghost.player.id();
+            // A split parent continues as the fragment with the bigger id.
+            let observed = enemies
+                .iter()
+                .find(|enemy| enemy.id() == id)
+                .map(|enemy| (enemy, false))
+                .or_else(|| {
+                    enemies
+                        .iter()
+                        .filter(|enemy| split_from(id, enemy))
+                        .max_by_key(|enemy| enemy.fragment_id())
+                        .map(|enemy| (enemy, true))
+                });
+            if let Some((enemy, split)) = observed {
+                let observed_v = enemy.point() - ghost.player.point();
+                self.opponent_tracker.observe(
+                    &ghost.player,
+                    observed_v,
+                    split,
+                    &context,
+                );
+            }
+        }
+        self.enemy_models = self.opponent_tracker.models();
+    }
+
+    fn link_fragments(&mut self, world: &World) -> (Vec<Player>, Vec<PlayerBlobId>) {
+        let mut enemies = world.enemies.clone();
+        let previous: Vec<_> = self.ghost_enemies
+            .values()
+            .filter(|ghost| ghost.last_seen == world.tick - 1)
+            .map(|ghost| ghost.player.clone())
+            .collect();
+        let gone = self.fragment_tracker.update(
+            world.tick,
+            &previous,
+            &mut enemies,
+            &self.state.viruses,
+            &self.config,
+        );
+        (enemies, gone)
+    }
+
+    fn update_enemies(&mut self, enemies: Vec<Player>) {
+        let tick = self.state.tick;
+        for mut enemy in enemies {
+            let linked = self.fragment_tracker.linked_at(enemy.id(), tick);
+            if linked {
+                // Velocity and TTF were inferred from the split, burst or fusion.
+            } else if let Some(ghost) = self.ghost_enemies.get(&enemy.id()).filter(
+                |ghost| ghost.last_seen == tick - 1,
+            )
+            {
+                enemy.set_ttf((ghost.player.ttf() - 1).max(0));
+            } else {
+                enemy.set_ttf((self.config.ticks_til_fusion as f64 * GHOST_TTF_FACTOR) as i64);
+            }
+            self.filter_velocity(&mut enemy, linked);
+            self.ghost_enemies.insert(
+                enemy.id(),
+                Ghost {
+                    player: enemy,
+                    last_seen: tick,
+                    uncertainty: 0.0,
+                },
+            );
+        }
+        self.extrapolate_ghosts();
+        let my_blobs = &self.state.my_blobs;
+        let config = &self.config;
+        let ghost_ticks = self.params.ghost_ticks;
+        let ghost_enemies = mem::replace(&mut self.ghost_enemies, BTreeMap::new());
+        self.ghost_enemies = ghost_enemies
+            .into_iter()
+            .filter(|(_, ghost)| {
+                ghost.last_seen >= tick - ghost_ticks &&
+                    (ghost.last_seen == tick ||
+                         !my_blobs.iter().any(|me| {
+                            me.can_see_safe(
+                                &ghost.player,
+                                my_blobs.len(),
+                                GHOST_VISIBILITY_FACTOR,
+                                config,
+                            )
+                        }))
+            })
+            .collect();
+        self.state.enemies = self.ghost_enemies
+            .values()
+            .map(|enemy| enemy.player.clone())
+            .collect();
+
+        let ghost_enemies = &self.ghost_enemies;
+        let velocity_filters = mem::replace(&mut self.velocity_filters, BTreeMap::new());
+        self.velocity_filters = velocity_filters
+            .into_iter()
+            .filter(|&(id, _)| ghost_enemies.contains_key(&id))
+            .collect();
+        let velocity_filters = &self.velocity_filters;
+        let min_confidence = self.params.min_target_confidence;
+        self.enemy_targets = ghost_enemies
+            .values()
+            .filter(|ghost| ghost.last_seen == tick)
+            .filter_map(|ghost| {
+                let (target, confidence) = velocity_filters
+                    .get(&ghost.player.id())?
+                    .target(&ghost.player, config)?;
+                if confidence >= min_confidence {
+                    Some((ghost.player.id(), target))
+                } else {
+                    None
+                }
+            })
+            .collect();
+    }
+
+    fn filter_velocity(&mut self, enemy: &mut Player, linked: bool) {
+        let tick = self.state.tick;
+        let config = &self.config;
+        if linked {
+            self.velocity_filters.insert(
+                enemy.id(),
+                VelocityFilter::new(tick, enemy, 0.0),
+            );
+            return;
+        }
+        if let Some(filter) = self.velocity_filters.get_mut(&enemy.id()) {
+            filter.update(tick, enemy, config);
+            enemy.set_v(filter.velocity());
+            enemy.update_is_fast(config);
+            return;
+        }
+        let filter = match ancestor_filter(
+            enemy.id(),
+            tick,
+            &self.fragment_tracker,
+            &self.velocity_filters,
+        ) {
+            Some(ancestor) => {
+                enemy.set_v(ancestor.velocity());
+                enemy.update_is_fast(config);
+                VelocityFilter::new(tick, enemy, ancestor.velocity_variance())
+            }
+            None => {
+                // Nothing to go on but the speed a blob like this can have.
+                let speed = if enemy.is_fast() {
+                    config.split_start_speed
+                } else {
+                    enemy.max_speed(config)
+                };
+                VelocityFilter::new(tick, enemy, speed.powi(2))
+            }
+        };
+        self.velocity_filters.insert(enemy.id(), filter);
+    }
+
+    fn extrapolate_ghosts(&mut self) {
+        let tick = self.state.tick;
+        let context = EnemyContext {
+            my_player_id: self.state.my_blobs.first().map_or(
+                <PlayerId>::max_value(),
+                |me| me.player_id(),
+            ),
+            players: &self.state.my_blobs,
+            food: &self.food,
+            food_grid: &self.food_grid,
+            eaten_food: &Default::default(),
+            config: &self.config,
+        };
+        for ghost in self.ghost_enemies.values_mut() {
+            if ghost.last_seen == tick {
+                continue;
+            }
+            if let Some(command) = GREEDY_CHASE.command(&ghost.player, &context) {
+                mechanic::apply_direct(&mut ghost.player, &command, &self.config);
+            }
+            mechanic::move_player(&mut ghost.player, &self.config);
+            ghost.uncertainty += ghost.player.max_speed(&self.config);
+        }
+    }
+}
+
+// Finds the filter of the closest ancestor that is still tracked, walking back along the lineage.
+fn ancestor_filter<'a>(
+    id: PlayerBlobId,
+    tick: Tick,
+    fragment_tracker: &FragmentTracker,
+    filters: &'a BTreeMap<PlayerBlobId, VelocityFilter>,
+) -> Option<&'a VelocityFilter> {
+    let (mut id, mut tick) = (id, tick + 1);
+    while let Some(lineage) = fragment_tracker.lineage(id) {
+        if lineage.tick >= tick {
+            break;
+        }
+        if let Some(filter) = filters.get(&lineage.parent) {
+            return Some(filter);
+        }
+        id = lineage.parent;
+        tick = lineage.tick;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strategy::enemy_model::SPLIT_ATTACK;
+    use strategy::mechanic::Mechanic;
+
+    fn observe<'a>(state: &State, config: &'a Config) -> World<'a> {
+        World {
+            tick: state.tick,
+            my_blobs: state.my_blobs.clone(),
+            food: vec![],
+            ejections: state.ejections.clone(),
+            viruses: state.viruses.clone(),
+            enemies: state
+                .enemies
+                .iter()
+                .map(|enemy| {
+                    let mut enemy = enemy.clone();
+                    enemy.set_v(Point::zero());
+                    enemy.set_fast(false);
+                    enemy.set_ttf(0);
+                    enemy
+                })
+                .collect(),
+            config,
+        }
+    }
+
+    #[test]
+    fn classifies_split_on_sight_as_split_attack() {
+        let config = Config::default();
+        let mut state = State::default();
+        state.my_blobs.push(Player::new(1, 0, Point::new(380.0, 500.0), 40.0, &config));
+        state.enemies.push(Player::new(2, 0, Point::new(500.0, 500.0), 300.0, &config));
+        let mut mechanic = Mechanic::new(&state, &config);
+        mechanic.set_default_enemy_model(&SPLIT_ATTACK);
+        let mut tracker = WorldTracker::new();
+        tracker.set_config(&config);
+        let flee = Command::from_point(Point::new(0.0, 500.0), &config);
+        for _ in 0..20 {
+            if mechanic.state.my_blobs.is_empty() {
+                break;
+            }
+            tracker.update(&observe(&mechanic.state, &config));
+            mechanic.tick(&flee, &[]);
+        }
+        let model = tracker
+            .enemy_models
+            .iter()
+            .find(|&&(player_id, _)| player_id == 2)
+            .map(|&(_, model)| model.name());
+        assert_eq!(model, Some(SPLIT_ATTACK.name()));
+    }
+
+    #[test]
+    fn orders_enemies_by_id() {
+        let config = Config::default();
+        let mut state = State::default();
+        state.my_blobs.push(Player::new(1, 0, Point::new(500.0, 500.0), 200.0, &config));
+        for &player_id in [7, 3, 5, 2, 6, 4].iter() {
+            let point = Point::new(400.0 + 30.0 * player_id as f64, 450.0);
+            state.enemies.push(Player::new(player_id, 0, point, 20.0, &config));
+        }
+        let mut tracker = WorldTracker::new();
+        tracker.set_config(&config);
+        tracker.update(&observe(&state, &config));
+        let ids: Vec<_> = tracker.state.enemies.iter().map(|enemy| enemy.player_id()).collect();
+        assert_eq!(ids, vec![2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn extrapolates_hidden_enemies_with_growing_uncertainty() {
+        const HIDDEN_TICKS: i64 = 10;
+        let config = Config::default();
+        let mut state = State::default();
+        state.my_blobs.push(Player::new(1, 0, Point::new(100.0, 100.0), 40.0, &config));
+        state.enemies.push(Player::new(2, 0, Point::new(700.0, 700.0), 100.0, &config));
+        let mut tracker = WorldTracker::new();
+        tracker.set_config(&config);
+        tracker.update(&observe(&state, &config));
+        state.tick += 1;
+        state.enemies[0].set_point(Point::new(697.0, 697.0));
+        tracker.update(&observe(&state, &config));
+        let seen = state.enemies.pop().unwrap();
+        for _ in 0..HIDDEN_TICKS {
+            state.tick += 1;
+            tracker.update(&observe(&state, &config));
+        }
+        let ghost = &tracker.ghost_enemies[&seen.id()];
+        assert_eq!(ghost.last_seen, 1);
+        assert!(ghost.player.point().dist(seen.point()) > 0.0);
+        let expected = HIDDEN_TICKS as f64 * seen.max_speed(&config);
+        assert!((ghost.uncertainty - expected).abs() < 1e-9);
+        assert_eq!(tracker.state.enemies.len(), 1);
+    }
+}
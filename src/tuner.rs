@@ -0,0 +1,344 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use rand::{Rng, SeedableRng, XorShiftRng};
+use serde_json;
+
+use config::Config;
+use server::{env_or, play_headless, PLAYER_COUNT};
+use strategy::*;
+
+const DEFAULT_GENERATIONS: usize = 50;
+const DEFAULT_GAMES: usize = 4;
+const DEFAULT_GAME_TICKS: Tick = 2000;
+const DEFAULT_MAX_PATHS: usize = 32;
+const DEFAULT_SEED: u32 = 0x5EED;
+const DEFAULT_OPPONENTS: &str = "my,my,my";
+const DEFAULT_TUNING_LOCATION: &str = "/var/tmp/tuning";
+
+const STEP_SIZE: f64 = 0.1;
+const STEP_DECAY: f64 = 0.602;
+const STEP_STABILITY: f64 = 10.0;
+const PERTURBATION_SIZE: f64 = 0.1;
+const PERTURBATION_DECAY: f64 = 0.101;
+
+#[derive(Debug, Clone, Default)]
+pub struct Candidate {
+    pub weights: ScoreWeights,
+    pub params: SearchParams,
+}
+
+struct Knob {
+    name: &'static str,
+    min: f64,
+    max: f64,
+    integer: bool,
+    get: fn(&Candidate) -> f64,
+    set: fn(&mut Candidate, f64),
+}
+
+lazy_static! {
+    static ref KNOBS: Vec<Knob> = vec![
+        Knob {
+            name: "DANGER",
+            min: -600.0,
+            max: -50.0,
+            integer: false,
+            get: |c| c.weights.danger,
+            set: |c, value| c.weights.danger = value,
+        },
+        Knob {
+            name: "GHOST_UNCERTAINTY",
+            min: 0.0,
+            max: 2.0,
+            integer: false,
+            get: |c| c.weights.ghost_uncertainty,
+            set: |c, value| c.weights.ghost_uncertainty = value,
+        },
+        Knob {
+            name: "PREY",
+            min: 0.0,
+            max: 2.0,
+            integer: false,
+            get: |c| c.weights.prey,
+            set: |c, value| c.weights.prey = value,
+        },
+        Knob {
+            name: "SPEED",
+            min: 0.0,
+            max: 0.05,
+            integer: false,
+            get: |c| c.weights.speed,
+            set: |c, value| c.weights.speed = value,
+        },
+        Knob {
+            name: "SMALL_BLOB",
+            min: -30.0,
+            max: 0.0,
+            integer: false,
+            get: |c| c.weights.small_blob,
+            set: |c, value| c.weights.small_blob = value,
+        },
+        Knob {
+            name: "WALL_MARGIN",
+            min: -20.0,
+            max: 0.0,
+            integer: false,
+            get: |c| c.weights.wall_margin,
+            set: |c, value| c.weights.wall_margin = value,
+        },
+        Knob {
+            name: "FOOD_PROXIMITY",
+            min: 0.0,
+            max: 20.0,
+            integer: false,
+            get: |c| c.weights.food_proximity,
+            set: |c, value| c.weights.food_proximity = value,
+        },
+        Knob {
+            name: "FUSION_READINESS",
+            min: 0.0,
+            max: 20.0,
+            integer: false,
+            get: |c| c.weights.fusion_readiness,
+            set: |c, value| c.weights.fusion_readiness = value,
+        },
+        Knob {
+            name: "GLOBAL_GOAL",
+            min: 0.0,
+            max: 40.0,
+            integer: false,
+            get: |c| c.weights.global_goal,
+            set: |c, value| c.weights.global_goal = value,
+        },
+        Knob {
+            name: "SIMULATION_DEPTH",
+            min: 3.0,
+            max: 10.0,
+            integer: true,
+            get: |c| c.params.simulation_depth as f64,
+            set: |c, value| c.params.simulation_depth = value as i64,
+        },
+        Knob {
+            name: "MIN_SKIPS",
+            min: 2.0,
+            max: 10.0,
+            integer: true,
+            get: |c| c.params.min_skips as f64,
+            set: |c, value| c.params.min_skips = value as i64,
+        },
+        Knob {
+            name: "GHOST_TICKS",
+            min: 0.0,
+            max: 150.0,
+            integer: true,
+            get: |c| c.params.ghost_ticks as f64,
+            set: |c, value| c.params.ghost_ticks = value as i64,
+        },
+        Knob {
+            name: "MIN_TARGET_CONFIDENCE",
+            min: 0.0,
+            max: 1.0,
+            integer: false,
+            get: |c| c.params.min_target_confidence,
+            set: |c, value| c.params.min_target_confidence = value,
+        },
+    ];
+}
+
+#[derive(Debug)]
+struct Settings {
+    generations: usize,
+    games: usize,
+    game_ticks: Tick,
+    max_paths: usize,
+    seed: u32,
+    strategy: String,
+    opponents: Vec<String>,
+    location: PathBuf,
+}
+
+pub fn run() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() > 1 {
+        eprintln!("usage: tuner [GENERATIONS]");
+        process::exit(2);
+    }
+    let opponents: Vec<String> = env::var("OPPONENTS")
+        .unwrap_or_else(|_| String::from(DEFAULT_OPPONENTS))
+        .split(',')
+        .map(String::from)
+        .collect();
+    if opponents.len() != PLAYER_COUNT as usize - 1 {
+        eprintln!("OPPONENTS must name {} strategies", PLAYER_COUNT - 1);
+        process::exit(2);
+    }
+    let settings = Settings {
+        generations: args.first().map_or(DEFAULT_GENERATIONS, |arg| {
+            arg.parse().expect("GENERATIONS is not a number")
+        }),
+        games: env_or("GAMES", DEFAULT_GAMES).max(1),
+        game_ticks: env_or("GAME_TICKS", DEFAULT_GAME_TICKS),
+        max_paths: env_or("MAX_PATHS", DEFAULT_MAX_PATHS),
+        seed: env_or("SEED", DEFAULT_SEED),
+        strategy: env::var("STRATEGY").unwrap_or_default(),
+        opponents,
+        location: PathBuf::from(env::var("TUNING_LOCATION").unwrap_or_else(|_| {
+            String::from(DEFAULT_TUNING_LOCATION)
+        })),
+    };
+    tune(&settings).expect("failed to write tuning results");
+}
+
+fn tune(settings: &Settings) -> io::Result<()> {
+    fs::create_dir_all(&settings.location)?;
+    let mut stats = File::create(settings.location.join("generations.tsv"))?;
+    let names: Vec<_> = KNOBS.iter().map(|knob| knob.name).collect();
+    writeln!(stats, "generation\tplus\tminus\tbest\t{}", names.join("\t"))?;
+
+    let mut rng = XorShiftRng::from_seed([settings.seed | 1, 0x1337_5EED, 0x7E57, 0xC0FF_EE00]);
+    let mut theta = encode(&Candidate::default());
+    let mut best_fitness = None;
+    for generation in 0..settings.generations {
+        let k = generation as f64;
+        let step = STEP_SIZE / (k + 1.0 + STEP_STABILITY).powf(STEP_DECAY);
+        let perturbation = PERTURBATION_SIZE / (k + 1.0).powf(PERTURBATION_DECAY);
+        let delta: Vec<f64> = theta
+            .iter()
+            .map(|_| if rng.gen() { 1.0 } else { -1.0 })
+            .collect();
+        let shifted = |sign: f64| -> Vec<f64> {
+            theta
+                .iter()
+                .zip(delta.iter())
+                .map(|(x, d)| x + sign * perturbation * d)
+                .collect()
+        };
+        let plus = decode(&shifted(1.0));
+        let minus = decode(&shifted(-1.0));
+        let seed = settings.seed.wrapping_add((generation * settings.games) as u32);
+        let plus_fitness = evaluate(&plus, settings, seed);
+        let minus_fitness = evaluate(&minus, settings, seed);
+
+        let gradient = (plus_fitness - minus_fitness) / (2.0 * perturbation);
+        for (x, d) in theta.iter_mut().zip(delta.iter()) {
+            *x = (*x + step * gradient / d).max(0.0).min(1.0);
+        }
+
+        for &(fitness, candidate) in [(plus_fitness, &plus), (minus_fitness, &minus)].iter() {
+            if best_fitness.map_or(true, |best| fitness > best) {
+                best_fitness = Some(fitness);
+                write_candidate(&settings.location, candidate)?;
+            }
+        }
+
+        let values: Vec<_> = KNOBS
+            .iter()
+            .map(|knob| format!("{}", (knob.get)(&decode(&theta))))
+            .collect();
+        writeln!(
+            stats,
+            "{}\t{:.4}\t{:.4}\t{:.4}\t{}",
+            generation,
+            plus_fitness,
+            minus_fitness,
+            best_fitness.unwrap_or(0.0),
+            values.join("\t")
+        )?;
+        stats.flush()?;
+        eprintln!(
+            "generation {}\tplus {:.4}\tminus {:.4}\tbest {:.4}",
+            generation,
+            plus_fitness,
+            minus_fitness,
+            best_fitness.unwrap_or(0.0)
+        );
+    }
+    Ok(())
+}
+
+fn evaluate(candidate: &Candidate, settings: &Settings, seed: u32) -> f64 {
+    let config = Config::default();
+    let mut total = 0.0;
+    for game in 0..settings.games {
+        let seat = game % PLAYER_COUNT as usize;
+        let mut opponents = settings.opponents.iter();
+        let mut strategies: Vec<_> = (0..PLAYER_COUNT as usize)
+            .map(|i| if i == seat {
+                new_strategy(&settings.strategy, candidate, settings.max_paths)
+            } else {
+                let name = opponents.next().expect("not enough opponents");
+                new_strategy(name, &Candidate::default(), settings.max_paths)
+            })
+            .collect();
+        let scores = play_headless(
+            seed.wrapping_add(game as u32),
+            settings.game_ticks,
+            &config,
+            &mut strategies,
+        );
+        let points = scores[&(seat as PlayerId + 1)];
+        let sum: i64 = scores.values().sum();
+        total += points as f64 / sum.max(1) as f64;
+    }
+    total / settings.games as f64
+}
+
+fn new_strategy(name: &str, candidate: &Candidate, max_paths: usize) -> Box<dyn Strategy> {
+    let scorer = Scorer::new(candidate.weights.clone());
+    let params = candidate.params.clone();
+    let budget = Budget::Paths(max_paths);
+    match name {
+        "evolution" => {
+            let mut strategy = EvolutionStrategy::new();
+            strategy.set_scorer(scorer);
+            strategy.set_search_params(params);
+            strategy.set_budget(budget);
+            Box::new(strategy)
+        }
+        "mcts" => {
+            let mut strategy = MctsStrategy::new();
+            strategy.set_scorer(scorer);
+            strategy.set_search_params(params);
+            strategy.set_budget(budget);
+            Box::new(strategy)
+        }
+        _ => {
+            let mut strategy = MyStrategy::new();
+            strategy.set_scorer(scorer);
+            strategy.set_search_params(params);
+            strategy.set_budget(budget);
+            Box::new(strategy)
+        }
+    }
+}
+
+fn encode(candidate: &Candidate) -> Vec<f64> {
+    KNOBS
+        .iter()
+        .map(|knob| ((knob.get)(candidate) - knob.min) / (knob.max - knob.min))
+        .collect()
+}
+
+fn decode(theta: &[f64]) -> Candidate {
+    let mut candidate = Candidate::default();
+    for (knob, x) in KNOBS.iter().zip(theta.iter()) {
+        let mut value = knob.min + x.max(0.0).min(1.0) * (knob.max - knob.min);
+        if knob.integer {
+            value = value.round();
+        }
+        (knob.set)(&mut candidate, value);
+    }
+    candidate
+}
+
+fn write_candidate(location: &Path, candidate: &Candidate) -> io::Result<()> {
+    let weights = File::create(location.join("best_weights.json"))?;
+    serde_json::to_writer_pretty(weights, &candidate.weights)?;
+    let params = File::create(location.join("best_search_params.json"))?;
+    serde_json::to_writer_pretty(params, &candidate.params)?;
+    Ok(())
+}